@@ -13,7 +13,7 @@ use criterion::{criterion_group, criterion_main};
 fn skynet_process(parent: Pid, num: usize, size: usize, div: usize) -> BoxFuture<'static, ()> {
     async move {
         if size == 1 {
-            send(parent, num);
+            send(parent, num).unwrap();
         } else {
             let new_size = size / div;
             let myself = myself();
@@ -32,7 +32,7 @@ fn skynet_process(parent: Pid, num: usize, size: usize, div: usize) -> BoxFuture
                 };
             }
 
-            send(parent, sum);
+            send(parent, sum).unwrap();
         }
     }
     .boxed()
@@ -3,7 +3,7 @@ use hastur::*;
 #[tokio::main]
 async fn main() {
     receive! {
-        a: _ => {
+        _a: _ => {
         },
         /*
         _: u32 => {
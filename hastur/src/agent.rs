@@ -0,0 +1,131 @@
+use std::any::Any;
+use std::marker::PhantomData;
+
+use crate::gen_server::{self, CallError, CallResult, Caller, CastResult, GenServer, CALL_TIMEOUT};
+use crate::kernel::ExitReason;
+use crate::pid::Pid;
+
+type Reply = Box<dyn Any + Send>;
+
+// the state is touched only by the agent process, so access is serialized
+struct Server<S>(S);
+
+enum AgentCall<S> {
+    Run(Box<dyn FnOnce(&mut S) -> Reply + Send>),
+    Stop,
+}
+
+impl<S: Send + 'static> GenServer for Server<S> {
+    type Args = Box<dyn FnOnce() -> S + Send>;
+    type Call = AgentCall<S>;
+    type Reply = Reply;
+    type Cast = Box<dyn FnOnce(&mut S) + Send>;
+
+    async fn init(init: Self::Args) -> Result<Self, ExitReason> {
+        Ok(Server(init()))
+    }
+
+    async fn handle_call(&mut self, request: AgentCall<S>, _: Caller) -> CallResult<Reply> {
+        match request {
+            AgentCall::Run(f) => CallResult::Reply(f(&mut self.0)),
+            AgentCall::Stop => CallResult::Stop(ExitReason::Normal, Box::new(())),
+        }
+    }
+
+    async fn handle_cast(&mut self, f: Self::Cast) -> CastResult {
+        f(&mut self.0);
+        CastResult::NoReply
+    }
+}
+
+// a process owning a state value of type S
+pub struct Agent<S> {
+    pid: Pid,
+    _state: PhantomData<fn() -> S>,
+}
+
+impl<S> Clone for Agent<S> {
+    fn clone(&self) -> Self {
+        *self
+    }
+}
+
+impl<S> Copy for Agent<S> {}
+
+impl<S> std::fmt::Debug for Agent<S> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_tuple("Agent").field(&self.pid).finish()
+    }
+}
+
+impl<S: Send + 'static> Agent<S> {
+    fn new(pid: Pid) -> Self {
+        Agent {
+            pid,
+            _state: PhantomData,
+        }
+    }
+
+    pub async fn start<F>(init: F) -> Result<Self, ExitReason>
+    where
+        F: FnOnce() -> S + Send + 'static,
+    {
+        gen_server::start::<Server<S>>(Box::new(init))
+            .await
+            .map(Self::new)
+    }
+
+    pub async fn start_link<F>(init: F) -> Result<Self, ExitReason>
+    where
+        F: FnOnce() -> S + Send + 'static,
+    {
+        gen_server::start_link::<Server<S>>(Box::new(init))
+            .await
+            .map(Self::new)
+    }
+
+    pub fn pid(&self) -> Pid {
+        self.pid
+    }
+
+    pub async fn get<R, F>(&self, f: F) -> Result<R, CallError>
+    where
+        R: Send + 'static,
+        F: FnOnce(&S) -> R + Send + 'static,
+    {
+        self.get_and_update(move |state| f(state)).await
+    }
+
+    pub async fn update<F>(&self, f: F) -> Result<(), CallError>
+    where
+        F: FnOnce(&mut S) + Send + 'static,
+    {
+        self.get_and_update(f).await
+    }
+
+    pub async fn get_and_update<R, F>(&self, f: F) -> Result<R, CallError>
+    where
+        R: Send + 'static,
+        F: FnOnce(&mut S) -> R + Send + 'static,
+    {
+        let run = AgentCall::Run(Box::new(move |state| Box::new(f(state)) as Reply));
+
+        gen_server::call::<Server<S>>(self.pid, run, CALL_TIMEOUT)
+            .await
+            .map(|reply| *reply.downcast::<R>().unwrap())
+    }
+
+    // does not wait for the update to be applied
+    pub fn cast<F>(&self, f: F)
+    where
+        F: FnOnce(&mut S) + Send + 'static,
+    {
+        gen_server::cast::<Server<S>>(self.pid, Box::new(f));
+    }
+
+    pub async fn stop(&self) -> Result<(), CallError> {
+        gen_server::call::<Server<S>>(self.pid, AgentCall::Stop, CALL_TIMEOUT)
+            .await
+            .map(|_| ())
+    }
+}
@@ -0,0 +1,228 @@
+use std::time::Duration;
+
+use derive_builder::Builder;
+
+use crate::gen_server::{self, CallError, CallResult, Caller, CastResult, GenServer, CALL_TIMEOUT};
+use crate::inbox::{Envelope, Exit};
+use crate::kernel::{self, ExitReason};
+use crate::pid::Pid;
+use crate::supervisor::{self, Child, ChildSpec, Intensity};
+
+#[derive(Clone, Builder, Debug)]
+pub struct DynamicSupervisorOpt {
+    // restarts allowed within the period before the supervisor gives up
+    #[builder(default = "3")]
+    intensity: usize,
+    #[builder(default = "Duration::from_secs(5)")]
+    period: Duration,
+    #[builder(setter(strip_option), default)]
+    max_children: Option<usize>,
+}
+
+impl Default for DynamicSupervisorOpt {
+    fn default() -> Self {
+        DynamicSupervisorOptBuilder::default().build().unwrap()
+    }
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum ChildError {
+    MaxChildren,
+    NotFound(Pid),
+    // the start function failed
+    Start(ExitReason),
+    Call(CallError),
+}
+
+impl std::fmt::Display for ChildError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ChildError::MaxChildren => f.write_str("max children reached"),
+            ChildError::NotFound(pid) => write!(f, "{} is not a child", pid),
+            ChildError::Start(reason) => write!(f, "start failed: {:?}", reason),
+            ChildError::Call(error) => write!(f, "{}", error),
+        }
+    }
+}
+
+impl std::error::Error for ChildError {}
+
+impl From<CallError> for ChildError {
+    fn from(error: CallError) -> Self {
+        ChildError::Call(error)
+    }
+}
+
+pub struct DynamicSupervisor {
+    intensity: Intensity,
+    max_children: Option<usize>,
+    // in start order
+    children: Vec<(Pid, ChildSpec)>,
+}
+
+pub enum DynamicCall {
+    StartChild(ChildSpec),
+    TerminateChild(Pid),
+    WhichChildren,
+}
+
+pub enum DynamicReply {
+    StartChild(Result<Pid, ChildError>),
+    TerminateChild(Result<(), ChildError>),
+    WhichChildren(Vec<Child>),
+}
+
+impl GenServer for DynamicSupervisor {
+    type Args = DynamicSupervisorOpt;
+    type Call = DynamicCall;
+    type Reply = DynamicReply;
+    type Cast = ();
+
+    async fn init(opt: DynamicSupervisorOpt) -> Result<Self, ExitReason> {
+        kernel::trap_exit(true);
+
+        Ok(DynamicSupervisor {
+            intensity: Intensity::new(opt.intensity, opt.period),
+            max_children: opt.max_children,
+            children: Vec::new(),
+        })
+    }
+
+    async fn handle_call(&mut self, request: DynamicCall, _: Caller) -> CallResult<DynamicReply> {
+        match request {
+            DynamicCall::StartChild(spec) => {
+                if self
+                    .max_children
+                    .is_some_and(|max| self.children.len() >= max)
+                {
+                    return CallResult::Reply(DynamicReply::StartChild(Err(
+                        ChildError::MaxChildren,
+                    )));
+                }
+
+                let result = match spec.start().await {
+                    Ok(pid) => {
+                        self.children.push((pid, spec));
+                        Ok(pid)
+                    }
+                    Err(reason) => Err(ChildError::Start(reason)),
+                };
+
+                CallResult::Reply(DynamicReply::StartChild(result))
+            }
+            DynamicCall::TerminateChild(pid) => {
+                let result = match self.children.iter().position(|(child, _)| *child == pid) {
+                    Some(index) => {
+                        let (pid, spec) = self.children.remove(index);
+                        supervisor::terminate_child(pid, spec.shutdown).await;
+                        Ok(())
+                    }
+                    None => Err(ChildError::NotFound(pid)),
+                };
+
+                CallResult::Reply(DynamicReply::TerminateChild(result))
+            }
+            DynamicCall::WhichChildren => CallResult::Reply(DynamicReply::WhichChildren(
+                self.children
+                    .iter()
+                    .map(|(pid, spec)| Child {
+                        id: spec.id().to_string(),
+                        pid: Some(*pid),
+                    })
+                    .collect(),
+            )),
+        }
+    }
+
+    async fn handle_cast(&mut self, _: ()) -> CastResult {
+        CastResult::NoReply
+    }
+
+    async fn handle_info(&mut self, message: Envelope) -> CastResult {
+        let Some(Exit(pid, reason)) = message.downcast::<Exit>() else {
+            return CastResult::NoReply;
+        };
+
+        let Some(index) = self.children.iter().position(|(child, _)| *child == pid) else {
+            return CastResult::NoReply;
+        };
+
+        tracing::trace!(
+            event = "child_exit",
+            id = self.children[index].1.id(),
+            ?pid,
+            ?reason
+        );
+
+        if !self.children[index].1.restart_on(&reason) {
+            self.children.remove(index);
+            return CastResult::NoReply;
+        }
+
+        loop {
+            if !self.intensity.restart() {
+                tracing::trace!(event = "shutdown", outcome = "intensity");
+                self.children.remove(index);
+                return CastResult::Stop(ExitReason::Shutdown);
+            }
+
+            if let Ok(pid) = self.children[index].1.start().await {
+                self.children[index].0 = pid;
+                return CastResult::NoReply;
+            }
+        }
+    }
+
+    async fn terminate(&mut self, _: ExitReason) {
+        while let Some((pid, spec)) = self.children.pop() {
+            supervisor::terminate_child(pid, spec.shutdown).await;
+        }
+    }
+}
+
+pub async fn start(opt: DynamicSupervisorOpt) -> Result<Pid, ExitReason> {
+    gen_server::start::<DynamicSupervisor>(opt).await
+}
+
+pub async fn start_link(opt: DynamicSupervisorOpt) -> Result<Pid, ExitReason> {
+    gen_server::start_link::<DynamicSupervisor>(opt).await
+}
+
+pub async fn start_child(supervisor: Pid, spec: ChildSpec) -> Result<Pid, ChildError> {
+    match gen_server::call::<DynamicSupervisor>(
+        supervisor,
+        DynamicCall::StartChild(spec),
+        CALL_TIMEOUT,
+    )
+    .await?
+    {
+        DynamicReply::StartChild(result) => result,
+        _ => unreachable!(),
+    }
+}
+
+pub async fn terminate_child(supervisor: Pid, pid: Pid) -> Result<(), ChildError> {
+    match gen_server::call::<DynamicSupervisor>(
+        supervisor,
+        DynamicCall::TerminateChild(pid),
+        CALL_TIMEOUT,
+    )
+    .await?
+    {
+        DynamicReply::TerminateChild(result) => result,
+        _ => unreachable!(),
+    }
+}
+
+pub async fn which_children(supervisor: Pid) -> Result<Vec<Child>, CallError> {
+    match gen_server::call::<DynamicSupervisor>(
+        supervisor,
+        DynamicCall::WhichChildren,
+        CALL_TIMEOUT,
+    )
+    .await?
+    {
+        DynamicReply::WhichChildren(children) => Ok(children),
+        _ => unreachable!(),
+    }
+}
@@ -0,0 +1,52 @@
+use crate::pid::Pid;
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum HasturError {
+    // called outside of a process
+    NoProcess,
+    // target process is not alive
+    NoProc(Pid),
+}
+
+impl std::fmt::Display for HasturError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            HasturError::NoProcess => f.write_str("not in a process"),
+            HasturError::NoProc(pid) => write!(f, "noproc {}", pid),
+        }
+    }
+}
+
+impl std::error::Error for HasturError {}
+
+// the message could not be delivered and is handed back
+#[derive(Clone, PartialEq)]
+pub struct SendError<T>(pub Pid, pub T);
+
+impl<T> SendError<T> {
+    pub fn into_inner(self) -> T {
+        self.1
+    }
+}
+
+impl<T> std::fmt::Debug for SendError<T> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_tuple("SendError")
+            .field(&self.0)
+            .finish_non_exhaustive()
+    }
+}
+
+impl<T> std::fmt::Display for SendError<T> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "noproc {}", self.0)
+    }
+}
+
+impl<T> std::error::Error for SendError<T> {}
+
+impl<T> From<SendError<T>> for HasturError {
+    fn from(error: SendError<T>) -> Self {
+        HasturError::NoProc(error.0)
+    }
+}
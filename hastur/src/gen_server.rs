@@ -0,0 +1,260 @@
+use std::time::Duration;
+
+use futures::{channel::oneshot, Future};
+
+use crate::inbox::{self, Down, Envelope, Exit};
+use crate::kernel::{self, ExitReason};
+use crate::pid::{myself, MonitorRef, Pid};
+use crate::spawn;
+
+// the default timeout of calls made by the behaviours built on gen_server
+pub(crate) const CALL_TIMEOUT: Duration = Duration::from_secs(5);
+
+// identifies a pending call, the tag is the monitor the caller holds on the server
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Caller {
+    pid: Pid,
+    tag: MonitorRef,
+}
+
+impl Caller {
+    pub fn pid(&self) -> Pid {
+        self.pid
+    }
+}
+
+#[derive(Debug, PartialEq)]
+pub enum CallResult<R> {
+    Reply(R),
+    // the reply is sent later with reply()
+    NoReply,
+    Stop(ExitReason, R),
+}
+
+#[derive(Debug, PartialEq)]
+pub enum CastResult {
+    NoReply,
+    Stop(ExitReason),
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum CallError {
+    // the server exited before replying
+    Exit(ExitReason),
+    Timeout,
+}
+
+impl std::fmt::Display for CallError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            CallError::Exit(reason) => write!(f, "server exited: {:?}", reason),
+            CallError::Timeout => f.write_str("timeout"),
+        }
+    }
+}
+
+impl std::error::Error for CallError {}
+
+struct Call<Q> {
+    caller: Caller,
+    request: Q,
+}
+
+struct Reply<R> {
+    tag: MonitorRef,
+    reply: R,
+}
+
+struct Cast<C>(C);
+
+pub trait GenServer: Send + Sized + 'static {
+    type Args: Send + 'static;
+    type Call: Send + 'static;
+    type Reply: Send + 'static;
+    type Cast: Send + 'static;
+
+    fn init(args: Self::Args) -> impl Future<Output = Result<Self, ExitReason>> + Send;
+
+    fn handle_call(
+        &mut self,
+        request: Self::Call,
+        caller: Caller,
+    ) -> impl Future<Output = CallResult<Self::Reply>> + Send;
+
+    fn handle_cast(&mut self, request: Self::Cast) -> impl Future<Output = CastResult> + Send;
+
+    fn handle_info(&mut self, message: Envelope) -> impl Future<Output = CastResult> + Send {
+        let _ = message;
+        async { CastResult::NoReply }
+    }
+
+    fn terminate(&mut self, reason: ExitReason) -> impl Future<Output = ()> + Send {
+        let _ = reason;
+        async {}
+    }
+}
+
+async fn serve<S: GenServer>(
+    args: S::Args,
+    parent: Pid,
+    started: oneshot::Sender<Result<(), ExitReason>>,
+) {
+    let mut server = match S::init(args).await {
+        Ok(server) => {
+            let _ = started.send(Ok(()));
+            server
+        }
+        Err(reason) => {
+            let _ = started.send(Err(reason.clone()));
+            kernel::exit(reason).await;
+            unreachable!();
+        }
+    };
+
+    let reason = loop {
+        let envelope = inbox::__receive().await;
+
+        let stop = if envelope.is::<Call<S::Call>>() {
+            let Call { caller, request } = envelope.downcast::<Call<S::Call>>().unwrap();
+
+            match server.handle_call(request, caller).await {
+                CallResult::Reply(reply) => {
+                    self::reply(&caller, reply);
+                    None
+                }
+                CallResult::NoReply => None,
+                CallResult::Stop(reason, reply) => {
+                    self::reply(&caller, reply);
+                    Some(reason)
+                }
+            }
+        } else if envelope.is::<Cast<S::Cast>>() {
+            let Cast(request) = envelope.downcast::<Cast<S::Cast>>().unwrap();
+
+            match server.handle_cast(request).await {
+                CastResult::NoReply => None,
+                CastResult::Stop(reason) => Some(reason),
+            }
+        } else if envelope
+            .downcast_ref::<Exit>()
+            .is_some_and(|exit| exit.0 == parent)
+        {
+            // trapped exit from the parent stops the server
+            let Exit(_, reason) = envelope.downcast::<Exit>().unwrap();
+            Some(reason)
+        } else {
+            match server.handle_info(envelope).await {
+                CastResult::NoReply => None,
+                CastResult::Stop(reason) => Some(reason),
+            }
+        };
+
+        if let Some(reason) = stop {
+            break reason;
+        }
+    };
+
+    tracing::trace!(event = "terminate", ?reason);
+
+    server.terminate(reason.clone()).await;
+    kernel::exit(reason).await;
+}
+
+async fn start_int<S: GenServer>(args: S::Args, link: bool) -> Result<Pid, ExitReason> {
+    let parent = myself();
+    let (started, receiver) = oneshot::channel();
+
+    let proc = serve::<S>(args, parent, started);
+    let pid = if link {
+        spawn::spawn_link(proc)
+    } else {
+        spawn::spawn(proc)
+    };
+
+    match receiver.await {
+        Ok(Ok(())) => Ok(pid),
+        Ok(Err(reason)) => Err(reason),
+        Err(_) => Err(ExitReason::NoProc(pid)),
+    }
+}
+
+// waits for init to complete
+pub async fn start<S: GenServer>(args: S::Args) -> Result<Pid, ExitReason> {
+    start_int::<S>(args, false).await
+}
+
+pub async fn start_link<S: GenServer>(args: S::Args) -> Result<Pid, ExitReason> {
+    start_int::<S>(args, true).await
+}
+
+pub async fn call<S: GenServer>(
+    server: Pid,
+    request: S::Call,
+    timeout: Duration,
+) -> Result<S::Reply, CallError> {
+    // the reply and the down carry the tag, the messages already in the
+    // mailbox are not scanned for them
+    let mark = inbox::mark();
+
+    // a dead server is reported by the monitor with noproc
+    let tag = kernel::monitor(server);
+
+    let caller = Caller { pid: myself(), tag };
+    let _ = inbox::send(server, Call { caller, request });
+
+    let tagged = |envelope: &Envelope| {
+        envelope
+            .downcast_ref::<Reply<S::Reply>>()
+            .is_some_and(|reply| reply.tag == tag)
+            || envelope
+                .downcast_ref::<Down>()
+                .is_some_and(|down| down.monitor_ref == tag)
+    };
+
+    let envelope = inbox::receive_marked(mark, tagged, Some(timeout)).await;
+
+    let result = match envelope {
+        Some(envelope) if envelope.is::<Reply<S::Reply>>() => {
+            Ok(envelope.downcast::<Reply<S::Reply>>().unwrap().reply)
+        }
+        Some(envelope) => Err(CallError::Exit(envelope.downcast::<Down>().unwrap().reason)),
+        None => Err(CallError::Timeout),
+    };
+
+    // a down is sent only while the monitor is held and a reply only while
+    // it is tracked, so the mailbox is cleaned only when one of them may be
+    // there. they are newer than the mark.
+    let flush = match result {
+        Ok(_) => !kernel::demonitor(tag, false),
+        Err(CallError::Timeout) => {
+            kernel::demonitor(tag, false);
+            true
+        }
+        Err(CallError::Exit(_)) => false,
+    };
+
+    if flush {
+        inbox::flush_marked(mark, tagged);
+    }
+    inbox::release(mark);
+
+    result
+}
+
+pub fn cast<S: GenServer>(server: Pid, request: S::Cast) {
+    let _ = inbox::send(server, Cast(request));
+}
+
+// the tag works as an alias: a caller that gave up on the call has released
+// its monitor and the reply is dropped
+pub fn reply<R: Send + 'static>(caller: &Caller, reply: R) {
+    kernel::while_monitoring(&caller.pid, &caller.tag, || {
+        let _ = inbox::send(
+            caller.pid,
+            Reply {
+                tag: caller.tag,
+                reply,
+            },
+        );
+    });
+}
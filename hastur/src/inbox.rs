@@ -1,23 +1,35 @@
 use atomic_refcell::AtomicRefCell;
 
 use futures::{
-    future::{poll_fn, Future},
+    future::{poll_fn, BoxFuture, Fuse, Future, FutureExt},
+    select_biased,
     task::{AtomicWaker, Poll},
 };
 use std::any::{Any, TypeId};
 use std::collections::VecDeque;
+use std::sync::atomic::{AtomicU64, AtomicUsize, Ordering};
+use std::time::Duration;
 
+use crate::error::SendError;
 use crate::kernel::ExitReason;
-use crate::pid::{myself, Pid};
+use crate::pid::{myself, MonitorRef, Pid};
+use crate::timer::{self, IntoTimeout};
 use crossbeam::queue::SegQueue;
 
 use dashmap::DashMap;
 
 use tracing::{self, instrument};
 
-#[derive(Clone, Copy, Debug, PartialEq)]
+#[derive(Clone, Debug, PartialEq)]
 pub struct Exit(pub Pid, pub ExitReason);
 
+#[derive(Clone, Debug, PartialEq)]
+pub struct Down {
+    pub monitor_ref: MonitorRef,
+    pub pid: Pid,
+    pub reason: ExitReason,
+}
+
 pub struct Envelope {
     size: usize,
     message: Box<dyn Any>,
@@ -43,7 +55,7 @@ impl Envelope {
     }
 
     pub fn id(&self) -> TypeId {
-        self.message.type_id()
+        (*self.message).type_id()
     }
 
     pub fn is<T: Any>(&self) -> bool {
@@ -53,6 +65,19 @@ impl Envelope {
     pub fn size(&self) -> usize {
         self.size
     }
+
+    // receive! takes the message out of the envelope to check a guard and
+    // gives it back, without reallocation, when the guard fails
+    pub fn __into_box<T: Any>(self) -> Box<T> {
+        self.message.downcast::<T>().unwrap()
+    }
+
+    pub fn __from_box<T: Send + 'static>(message: Box<T>) -> Self {
+        Self {
+            size: std::mem::size_of::<T>(),
+            message,
+        }
+    }
 }
 
 impl<T: PartialEq + Send + 'static> PartialEq<T> for Envelope {
@@ -81,6 +106,15 @@ struct Inbox {
     save_queue: AtomicRefCell<SaveQueue>,
     exit_queue: SegQueue<Exit>,
 
+    // kept aside, the save queue may be borrowed by its owner
+    save_queue_len: AtomicUsize,
+    size: AtomicUsize,
+
+    // the current mark, NOMARK once the save queue is taken or restored
+    mark: AtomicU64,
+    // the messages at the front of the save queue newer than the mark
+    marked: AtomicUsize,
+
     waker: AtomicWaker,
 }
 
@@ -97,8 +131,25 @@ impl Inbox {
             waker,
             save_queue,
             exit_queue,
+            save_queue_len: AtomicUsize::new(0),
+            size: AtomicUsize::new(0),
+            mark: AtomicU64::new(NOMARK),
+            marked: AtomicUsize::new(0),
         }
     }
+
+    fn push(&self, envelope: Envelope) {
+        self.size.fetch_add(envelope.size(), Ordering::Relaxed);
+        self.message_queue.push(envelope);
+        self.waker.wake();
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub(crate) struct InboxInfo {
+    pub message_queue_len: usize,
+    pub save_queue_len: usize,
+    pub size: usize,
 }
 
 lazy_static::lazy_static! {
@@ -117,22 +168,27 @@ pub(crate) fn drop(pid: &Pid) {
 
 static NOPROC: &str = "noproc";
 
-pub fn send<T: Send + 'static>(to: Pid, message: T) {
+const NOMARK: u64 = 0;
+static MARKGEN: AtomicU64 = AtomicU64::new(NOMARK + 1);
+
+pub fn send<T: Send + 'static>(to: Pid, message: T) -> Result<(), SendError<T>> {
     if let Some(inbox) = PINBOX.get(&to) {
-        inbox.message_queue.push(Envelope::new(message));
-        inbox.waker.wake();
+        inbox.push(Envelope::new(message));
+        Ok(())
     } else {
-        tracing::warn!(NOPROC);
+        tracing::trace!(event = "send", what = NOPROC, ?to);
+        Err(SendError(to, message))
     }
 }
 
 #[instrument(level = "debug", skip(envelope))]
-pub fn send_raw(to: Pid, envelope: Envelope) {
+pub fn send_raw(to: Pid, envelope: Envelope) -> Result<(), SendError<Envelope>> {
     if let Some(inbox) = PINBOX.get(&to) {
-        inbox.message_queue.push(envelope);
-        inbox.waker.wake();
+        inbox.push(envelope);
+        Ok(())
     } else {
-        tracing::warn!(NOPROC);
+        tracing::trace!(event = "send_raw", what = NOPROC, ?to);
+        Err(SendError(to, envelope))
     }
 }
 
@@ -140,18 +196,31 @@ pub fn __receive() -> impl Future<Output = Envelope> {
     let myself = myself();
 
     poll_fn(move |context| {
-        let inbox = PINBOX.get(&myself).expect(NOPROC);
+        // the inbox is dropped only after the process is no longer polled
+        let Some(inbox) = PINBOX.get(&myself) else {
+            return Poll::Pending;
+        };
 
         if !inbox.exit_queue.is_empty() {
             // yield if there are exits
             Poll::Pending
         } else {
             match inbox.save_queue.borrow_mut().pop_back() {
-                Some(envelope) => Poll::Ready(envelope),
+                Some(envelope) => {
+                    if inbox.mark.load(Ordering::Relaxed) != NOMARK {
+                        inbox.mark.store(NOMARK, Ordering::Relaxed);
+                    }
+
+                    inbox.save_queue_len.fetch_sub(1, Ordering::Relaxed);
+                    inbox.size.fetch_sub(envelope.size(), Ordering::Relaxed);
+                    Poll::Ready(envelope)
+                }
 
                 None => {
                     if !inbox.message_queue.is_empty() {
-                        Poll::Ready(inbox.message_queue.pop().unwrap())
+                        let envelope = inbox.message_queue.pop().unwrap();
+                        inbox.size.fetch_sub(envelope.size(), Ordering::Relaxed);
+                        Poll::Ready(envelope)
                     } else {
                         inbox.waker.register(context.waker());
                         Poll::Pending
@@ -163,12 +232,239 @@ pub fn __receive() -> impl Future<Output = Envelope> {
 }
 
 pub fn __selective_restore(mut save: SaveQueue) {
-    let inbox = PINBOX.get(&myself()).expect(NOPROC);
+    let Some(inbox) = PINBOX.get(&myself()) else {
+        return;
+    };
+
+    if !save.is_empty() {
+        inbox.mark.store(NOMARK, Ordering::Relaxed);
+    }
+
+    let size = save.iter().map(Envelope::size).sum();
+    inbox
+        .save_queue_len
+        .fetch_add(save.len(), Ordering::Relaxed);
+    inbox.size.fetch_add(size, Ordering::Relaxed);
 
     inbox.save_queue.borrow_mut().append(&mut save);
 }
 
-pub fn send_exit(to: &Pid, exit: Exit) -> bool {
+// the timeout of a receive! with after, it fires once
+pub struct __After(Fuse<BoxFuture<'static, ()>>);
+
+impl __After {
+    pub fn new<T: IntoTimeout>(timeout: T) -> Self {
+        Self(timer::sleep(timeout.into_timeout()).fuse())
+    }
+
+    // messages already in the mailbox win over the timeout, so a zero
+    // timeout checks the mailbox without waiting
+    pub async fn receive(&mut self) -> Option<Envelope> {
+        select_biased! {
+            envelope = __receive().fuse() => Some(envelope),
+            _ = &mut self.0 => None,
+        }
+    }
+}
+
+// waits for the first message matching f, the others stay in the mailbox in order
+pub(crate) async fn receive_match<F: Fn(&Envelope) -> bool>(
+    f: F,
+    timeout: Option<Duration>,
+) -> Option<Envelope> {
+    let mut save_queue = SaveQueue::new();
+
+    let mut timeout = match timeout {
+        Some(timeout) => timer::sleep(timeout),
+        None => futures::future::pending().boxed(),
+    }
+    .fuse();
+
+    let result = loop {
+        select_biased! {
+            envelope = __receive().fuse() => {
+                if f(&envelope) {
+                    break Some(envelope);
+                } else {
+                    save_queue.push_front(envelope);
+                }
+            },
+
+            _ = timeout => {
+                break None;
+            }
+        }
+    };
+
+    __selective_restore(save_queue);
+
+    result
+}
+
+// a point in the mailbox of the calling process: the messages received
+// before it are in the save queue, the newer ones in the message queue.
+// a ref made after the mark cannot be carried by a message older than it.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub(crate) struct Mark(u64);
+
+pub(crate) fn mark() -> Mark {
+    let mark = Mark(MARKGEN.fetch_add(1, Ordering::Relaxed));
+
+    if let Some(inbox) = PINBOX.get(&myself()) {
+        let mut save_queue = inbox.save_queue.borrow_mut();
+
+        while let Some(envelope) = inbox.message_queue.pop() {
+            save_queue.push_front(envelope);
+            inbox.save_queue_len.fetch_add(1, Ordering::Relaxed);
+        }
+
+        inbox.marked.store(0, Ordering::Relaxed);
+        inbox.mark.store(mark.0, Ordering::Relaxed);
+    }
+
+    mark
+}
+
+pub(crate) fn release(mark: Mark) {
+    if let Some(inbox) = PINBOX.get(&myself()) {
+        let _ = inbox
+            .mark
+            .compare_exchange(mark.0, NOMARK, Ordering::Relaxed, Ordering::Relaxed);
+    }
+}
+
+// pops the first new message matching f, the others are saved
+fn receive_new<F: Fn(&Envelope) -> bool>(f: F) -> impl Future<Output = Envelope> {
+    let myself = myself();
+
+    poll_fn(move |context| {
+        let Some(inbox) = PINBOX.get(&myself) else {
+            return Poll::Pending;
+        };
+
+        if !inbox.exit_queue.is_empty() {
+            return Poll::Pending;
+        }
+
+        inbox.waker.register(context.waker());
+
+        while let Some(envelope) = inbox.message_queue.pop() {
+            if f(&envelope) {
+                inbox.size.fetch_sub(envelope.size(), Ordering::Relaxed);
+                return Poll::Ready(envelope);
+            }
+
+            inbox.save_queue.borrow_mut().push_front(envelope);
+            inbox.save_queue_len.fetch_add(1, Ordering::Relaxed);
+            inbox.marked.fetch_add(1, Ordering::Relaxed);
+        }
+
+        Poll::Pending
+    })
+}
+
+// as receive_match, for a message carrying a ref made after the mark. while
+// the mark holds only the messages newer than it are scanned, the older ones
+// are left alone in the save queue.
+pub(crate) async fn receive_marked<F: Fn(&Envelope) -> bool>(
+    mark: Mark,
+    f: F,
+    timeout: Option<Duration>,
+) -> Option<Envelope> {
+    let marked = PINBOX
+        .get(&myself())
+        .is_some_and(|inbox| inbox.mark.load(Ordering::Relaxed) == mark.0);
+
+    if !marked {
+        return receive_match(f, timeout).await;
+    }
+
+    let mut timeout = match timeout {
+        Some(timeout) => timer::sleep(timeout),
+        None => futures::future::pending().boxed(),
+    }
+    .fuse();
+
+    select_biased! {
+        envelope = receive_new(f).fuse() => Some(envelope),
+        _ = timeout => None,
+    }
+}
+
+// removes matching messages from the mailbox of the calling process.
+// pending messages are moved to the save queue to keep their order.
+pub(crate) fn flush<F: Fn(&Envelope) -> bool>(pid: &Pid, f: F) {
+    let Some(inbox) = PINBOX.get(pid) else {
+        return;
+    };
+    let mut save_queue = inbox.save_queue.borrow_mut();
+
+    inbox.mark.store(NOMARK, Ordering::Relaxed);
+
+    while let Some(envelope) = inbox.message_queue.pop() {
+        save_queue.push_front(envelope);
+    }
+
+    save_queue.retain(|envelope| {
+        let flush = f(envelope);
+
+        if flush {
+            inbox.size.fetch_sub(envelope.size(), Ordering::Relaxed);
+        }
+
+        !flush
+    });
+
+    inbox
+        .save_queue_len
+        .store(save_queue.len(), Ordering::Relaxed);
+}
+
+// as flush for the calling process, while the mark holds only the messages
+// newer than it are scanned
+pub(crate) fn flush_marked<F: Fn(&Envelope) -> bool>(mark: Mark, f: F) {
+    let myself = myself();
+
+    let Some(inbox) = PINBOX
+        .get(&myself)
+        .filter(|inbox| inbox.mark.load(Ordering::Relaxed) == mark.0)
+    else {
+        return flush(&myself, f);
+    };
+    let mut save_queue = inbox.save_queue.borrow_mut();
+
+    let mut marked = inbox.marked.load(Ordering::Relaxed);
+    while let Some(envelope) = inbox.message_queue.pop() {
+        save_queue.push_front(envelope);
+        marked += 1;
+    }
+
+    // the newer messages are put back in front of the older ones, in order
+    let newer: SaveQueue = save_queue.drain(..marked).collect();
+    for envelope in newer.into_iter().rev() {
+        if f(&envelope) {
+            inbox.size.fetch_sub(envelope.size(), Ordering::Relaxed);
+            marked -= 1;
+        } else {
+            save_queue.push_front(envelope);
+        }
+    }
+
+    inbox.marked.store(marked, Ordering::Relaxed);
+    inbox
+        .save_queue_len
+        .store(save_queue.len(), Ordering::Relaxed);
+}
+
+pub(crate) fn info(pid: &Pid) -> Option<InboxInfo> {
+    PINBOX.get(pid).map(|inbox| InboxInfo {
+        message_queue_len: inbox.message_queue.len(),
+        save_queue_len: inbox.save_queue_len.load(Ordering::Relaxed),
+        size: inbox.size.load(Ordering::Relaxed),
+    })
+}
+
+pub(crate) fn send_exit(to: &Pid, exit: Exit) -> bool {
     if let Some(inbox) = PINBOX.get(to) {
         inbox.exit_queue.push(exit);
         inbox.waker.wake();
@@ -181,7 +477,9 @@ pub fn send_exit(to: &Pid, exit: Exit) -> bool {
 
 pub(crate) fn receive_exit(pid: Pid) -> impl Future<Output = Exit> {
     poll_fn(move |context| {
-        let inbox = PINBOX.get(&pid).expect(NOPROC);
+        let Some(inbox) = PINBOX.get(&pid) else {
+            return Poll::Pending;
+        };
 
         if !inbox.exit_queue.is_empty() {
             Poll::Ready(inbox.exit_queue.pop().unwrap())
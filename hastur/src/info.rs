@@ -0,0 +1,40 @@
+use crate::inbox;
+use crate::kernel;
+use crate::pid::Pid;
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct ProcessInfo {
+    pub message_queue_len: usize,
+    pub save_queue_len: usize,
+    // bytes of queued envelopes, messages taken by a running receive are not counted
+    pub memory: usize,
+    pub links: Vec<Pid>,
+    pub monitors: Vec<Pid>,
+    pub monitored_by: Vec<Pid>,
+    pub trap_exit: bool,
+    pub registered_name: Option<String>,
+}
+
+pub fn process_info(pid: Pid) -> Option<ProcessInfo> {
+    let kernel = kernel::get(&pid).ok()?;
+    let inbox = inbox::info(&pid)?;
+
+    let registered_name = kernel.name().clone();
+
+    Some(ProcessInfo {
+        message_queue_len: inbox.message_queue_len,
+        save_queue_len: inbox.save_queue_len,
+        memory: inbox.size,
+        links: kernel.links(),
+        monitors: kernel.monitored(),
+        monitored_by: kernel.monitored_by(),
+        trap_exit: kernel.get_trap_exit(),
+        registered_name,
+    })
+}
+
+pub fn processes() -> Vec<Pid> {
+    let mut pids = kernel::pids();
+    pids.sort();
+    pids
+}
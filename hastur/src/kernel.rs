@@ -1,4 +1,6 @@
+use std::any::Any;
 use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex, MutexGuard};
 
 use futures::{channel::oneshot, future::pending};
 
@@ -7,25 +9,120 @@ use dashmap::{
     DashMap, DashSet,
 };
 
+use crate::error::HasturError;
 use crate::inbox;
 use crate::pid::{myself, MonitorRef, Pid};
+use crate::timer::{TimerRef, Wheel};
 
-#[derive(Clone, Copy, Debug, PartialEq)]
+trait Value: Any + Send + Sync + std::fmt::Debug {
+    fn as_any(&self) -> &dyn Any;
+    fn eq_value(&self, other: &dyn Value) -> bool;
+}
+
+impl<T: Any + Send + Sync + std::fmt::Debug + PartialEq> Value for T {
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+
+    fn eq_value(&self, other: &dyn Value) -> bool {
+        other.as_any().downcast_ref::<T>() == Some(self)
+    }
+}
+
+// application value carried by an exit reason
+#[derive(Clone)]
+pub struct Payload(Arc<dyn Value>);
+
+impl Payload {
+    pub fn new<T: Any + Send + Sync + std::fmt::Debug + PartialEq>(value: T) -> Self {
+        Self(Arc::new(value))
+    }
+
+    pub fn downcast_ref<T: Any>(&self) -> Option<&T> {
+        self.0.as_any().downcast_ref::<T>()
+    }
+
+    pub fn is<T: Any>(&self) -> bool {
+        self.0.as_any().is::<T>()
+    }
+}
+
+impl PartialEq for Payload {
+    fn eq(&self, other: &Self) -> bool {
+        self.0.eq_value(other.0.as_ref())
+    }
+}
+
+impl std::fmt::Debug for Payload {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        self.0.fmt(f)
+    }
+}
+
+#[derive(Clone, Debug, PartialEq)]
 pub enum ExitReason {
     Normal,
-    Custom,
+    Shutdown,
+    ShutdownWith(Payload),
+    Custom(Payload),
     NoProc(Pid),
-    Panic,
+    Panic(PanicInfo),
     Kill,
+    Killed,
     JoinError,
 }
 
+#[derive(Clone, Debug, PartialEq)]
+pub struct PanicInfo {
+    pub message: String,
+    pub location: Option<String>,
+}
+
+impl std::fmt::Display for PanicInfo {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match &self.location {
+            Some(location) => write!(f, "{} at {}", self.message, location),
+            None => f.write_str(&self.message),
+        }
+    }
+}
+
+impl ExitReason {
+    pub fn custom<T: Any + Send + Sync + std::fmt::Debug + PartialEq>(value: T) -> Self {
+        Self::Custom(Payload::new(value))
+    }
+
+    pub fn shutdown<T: Any + Send + Sync + std::fmt::Debug + PartialEq>(value: T) -> Self {
+        Self::ShutdownWith(Payload::new(value))
+    }
+}
+
+// lets main return the exit reason of the root process
+impl std::process::Termination for ExitReason {
+    fn report(self) -> std::process::ExitCode {
+        match self {
+            ExitReason::Normal | ExitReason::Shutdown | ExitReason::ShutdownWith(_) => {
+                std::process::ExitCode::SUCCESS
+            }
+            reason => {
+                eprintln!("Error: {:?}", reason);
+                std::process::ExitCode::FAILURE
+            }
+        }
+    }
+}
+
 pub struct Kernel {
     pid: Pid,
     linked: DashSet<Pid>,
     monitors: DashMap<MonitorRef, Pid>,
+    monitoring: DashMap<MonitorRef, Pid>,
     self_exit_sender: Option<oneshot::Sender<ExitReason>>,
     trap_exit: AtomicBool,
+    name: Mutex<Option<String>>,
+    groups: DashSet<String>,
+    wheel: Arc<Wheel>,
+    timers: DashSet<TimerRef>,
 }
 
 lazy_static::lazy_static! {
@@ -34,33 +131,43 @@ lazy_static::lazy_static! {
     };
 }
 
-const NOKERNEL: &str = "nokernel";
+pub(crate) fn get(pid: &Pid) -> Result<Ref<'static, Pid, Kernel>, HasturError> {
+    PKERNEL.get(pid).ok_or(HasturError::NoProc(*pid))
+}
 
-pub(crate) fn get(pid: &Pid) -> Ref<'static, Pid, Kernel> {
-    PKERNEL.get(pid).expect(NOKERNEL)
+pub(crate) fn get_mut(pid: &Pid) -> Result<RefMut<'static, Pid, Kernel>, HasturError> {
+    PKERNEL.get_mut(pid).ok_or(HasturError::NoProc(*pid))
 }
 
-pub(crate) fn get_mut(pid: &Pid) -> RefMut<'static, Pid, Kernel> {
-    PKERNEL.get_mut(pid).expect(NOKERNEL)
+pub(crate) fn pids() -> Vec<Pid> {
+    PKERNEL.iter().map(|entry| *entry.key()).collect()
 }
 
 pub(crate) fn place(pid: Pid, kernel: Kernel) {
     PKERNEL.insert(pid, kernel);
 }
 
-pub(crate) fn remove(pid: &Pid) -> Kernel {
-    PKERNEL.remove(pid).expect(NOKERNEL).1
+pub(crate) fn remove(pid: &Pid) -> Result<Kernel, HasturError> {
+    PKERNEL
+        .remove(pid)
+        .map(|(_, kernel)| kernel)
+        .ok_or(HasturError::NoProc(*pid))
 }
 
 impl Kernel {
-    pub fn new(pid: Pid, self_exit_sender: oneshot::Sender<ExitReason>) -> Self {
+    pub fn new(pid: Pid, self_exit_sender: oneshot::Sender<ExitReason>, wheel: Arc<Wheel>) -> Self {
         Kernel {
             pid,
             linked: DashSet::new(),
             monitors: DashMap::new(),
+            monitoring: DashMap::new(),
 
             trap_exit: AtomicBool::new(false),
             self_exit_sender: Some(self_exit_sender),
+            name: Mutex::new(None),
+            groups: DashSet::new(),
+            wheel,
+            timers: DashSet::new(),
         }
     }
 
@@ -72,6 +179,24 @@ impl Kernel {
         self.trap_exit.load(Ordering::Relaxed)
     }
 
+    pub fn name(&self) -> MutexGuard<'_, Option<String>> {
+        self.name.lock().unwrap()
+    }
+
+    pub fn join(&self, group: String) {
+        self.groups.insert(group);
+    }
+
+    pub fn leave(&self, group: &str) {
+        self.groups.remove(group);
+    }
+
+    pub fn for_each_group<F: Fn(&str)>(&self, f: F) {
+        self.groups.iter().for_each(|group| {
+            f(&group);
+        });
+    }
+
     pub fn exit(&mut self, reason: ExitReason) {
         if let Some(sender) = self.self_exit_sender.take() {
             let _ = sender.send(reason);
@@ -83,8 +208,16 @@ impl Kernel {
     pub fn link(&self, pid: Pid) {
         tracing::trace!(event = "link", pid1 = ?self.pid, pid2 = ?pid);
 
-        get(&pid).linked.insert(self.pid);
-        self.linked.insert(pid);
+        if let Ok(kernel) = get(&pid) {
+            kernel.linked.insert(self.pid);
+            self.linked.insert(pid);
+        }
+    }
+
+    pub fn unlink(&self, pid: &Pid) {
+        tracing::trace!(event = "unlink", pid1 = ?self.pid, pid2 = ?pid);
+
+        self.linked.remove(pid);
     }
 
     pub fn for_each_linked<F: Fn(&Pid)>(&self, f: F) {
@@ -96,28 +229,209 @@ impl Kernel {
     pub fn monitor(&self, monitor_ref: MonitorRef, monitor_pid: Pid) {
         self.monitors.insert(monitor_ref, monitor_pid);
     }
+
+    pub fn demonitor(&self, monitor_ref: &MonitorRef) {
+        self.monitors.remove(monitor_ref);
+    }
+
+    pub fn for_each_monitor<F: Fn(&MonitorRef, &Pid)>(&self, f: F) {
+        self.monitors.iter().for_each(|entry| {
+            f(entry.key(), entry.value());
+        });
+    }
+
+    pub fn for_each_monitoring<F: Fn(&MonitorRef, &Pid)>(&self, f: F) {
+        self.monitoring.iter().for_each(|entry| {
+            f(entry.key(), entry.value());
+        });
+    }
+
+    pub fn links(&self) -> Vec<Pid> {
+        self.linked.iter().map(|pid| *pid).collect()
+    }
+
+    pub fn monitored(&self) -> Vec<Pid> {
+        self.monitoring.iter().map(|entry| *entry.value()).collect()
+    }
+
+    pub fn monitored_by(&self) -> Vec<Pid> {
+        self.monitors.iter().map(|entry| *entry.value()).collect()
+    }
+
+    pub fn track_monitor(&self, monitor_ref: MonitorRef, pid: Pid) {
+        self.monitoring.insert(monitor_ref, pid);
+    }
+
+    pub fn untrack_monitor(&self, monitor_ref: &MonitorRef) -> Option<Pid> {
+        self.monitoring.remove(monitor_ref).map(|(_, pid)| pid)
+    }
+
+    pub fn wheel(&self) -> &Arc<Wheel> {
+        &self.wheel
+    }
+
+    pub fn track_timer(&self, timer_ref: TimerRef) {
+        self.timers.insert(timer_ref);
+    }
+
+    pub fn untrack_timer(&self, timer_ref: &TimerRef) {
+        self.timers.remove(timer_ref);
+    }
+
+    pub fn tracks_timer(&self, timer_ref: &TimerRef) -> bool {
+        self.timers.contains(timer_ref)
+    }
+
+    pub fn for_each_timer<F: Fn(&TimerRef)>(&self, f: F) {
+        self.timers.iter().for_each(|timer_ref| {
+            f(&timer_ref);
+        });
+    }
 }
 
 pub fn link(to: Pid) {
     let myself = myself();
 
-    if let Some(kernel) = PKERNEL.get(&to) {
+    if let Ok(kernel) = get(&to) {
         kernel.link(myself);
     } else {
-        inbox::send_exit(&myself, inbox::Exit(myself, ExitReason::NoProc(to)));
+        signal(&myself, inbox::Exit(to, ExitReason::NoProc(to)));
+    }
+}
+
+// exits to a trapping process go straight to the mailbox, so they stay in
+// order with messages sent before and after by the same process
+pub(crate) fn signal(to: &Pid, exit: inbox::Exit) {
+    let trapped = exit.1 != ExitReason::Kill && get(to).is_ok_and(|kernel| kernel.get_trap_exit());
+
+    if trapped {
+        let _ = inbox::send(*to, exit);
+    } else {
+        inbox::send_exit(to, exit);
+    }
+}
+
+// delivers Down to the monitoring process unless it has already demonitored.
+// the message is sent while the monitoring entry is locked, so demonitor
+// either wins and no Down is sent, or Down is already in the mailbox.
+pub(crate) fn notify_monitor(monitor_pid: &Pid, monitor_ref: &MonitorRef, down: inbox::Down) {
+    if let Ok(kernel) = get(monitor_pid) {
+        kernel.monitoring.remove_if(monitor_ref, |_, _| {
+            let _ = inbox::send(*monitor_pid, down);
+            true
+        });
+    }
+}
+
+// runs f while the monitoring process holds the monitor, a concurrent
+// demonitor waits for f to finish
+pub(crate) fn while_monitoring<F: FnOnce()>(
+    monitor_pid: &Pid,
+    monitor_ref: &MonitorRef,
+    f: F,
+) -> bool {
+    get(monitor_pid).is_ok_and(|kernel| {
+        kernel
+            .monitoring
+            .get(monitor_ref)
+            .map(|_entry| f())
+            .is_some()
+    })
+}
+
+pub fn monitor(pid: Pid) -> MonitorRef {
+    let myself = myself();
+    let monitor_ref = MonitorRef::new();
+
+    tracing::trace!(event = "monitor", ?monitor_ref, ?pid);
+
+    if let Ok(kernel) = get(&myself) {
+        kernel.track_monitor(monitor_ref, pid);
     }
+
+    let found = get(&pid)
+        .map(|kernel| kernel.monitor(monitor_ref, myself))
+        .is_ok();
+
+    if !found {
+        notify_monitor(
+            &myself,
+            &monitor_ref,
+            inbox::Down {
+                monitor_ref,
+                pid,
+                reason: ExitReason::NoProc(pid),
+            },
+        );
+    }
+
+    monitor_ref
+}
+
+pub fn demonitor(monitor_ref: MonitorRef, flush: bool) -> bool {
+    let myself = myself();
+
+    tracing::trace!(event = "demonitor", ?monitor_ref, flush);
+
+    let pid = get(&myself)
+        .ok()
+        .and_then(|kernel| kernel.untrack_monitor(&monitor_ref));
+
+    if let Some(pid) = pid {
+        if let Ok(kernel) = get(&pid) {
+            kernel.demonitor(&monitor_ref);
+        }
+    }
+
+    if flush {
+        inbox::flush(&myself, |envelope| {
+            envelope
+                .downcast_ref::<inbox::Down>()
+                .is_some_and(|down| down.monitor_ref == monitor_ref)
+        });
+    }
+
+    pid.is_some()
+}
+
+pub fn unlink(from: Pid) {
+    let myself = myself();
+
+    if let Ok(kernel) = get(&myself) {
+        kernel.unlink(&from);
+    }
+
+    if let Ok(kernel) = get(&from) {
+        kernel.unlink(&myself);
+    }
+}
+
+pub fn kill(pid: Pid, reason: ExitReason) {
+    let myself = myself();
+
+    tracing::trace!(event = "kill", ?pid, ?reason);
+
+    signal(&pid, inbox::Exit(myself, reason));
+}
+
+pub fn is_alive(pid: Pid) -> bool {
+    PKERNEL.contains_key(&pid)
 }
 
 pub fn trap_exit(value: bool) {
-    get_mut(&myself()).trap_exit(value);
+    if let Ok(kernel) = get(&myself()) {
+        kernel.trap_exit(value);
+    }
 }
 
 pub fn get_trap_exit() -> bool {
-    get_mut(&myself()).get_trap_exit()
+    get(&myself()).is_ok_and(|kernel| kernel.get_trap_exit())
 }
 
 pub async fn exit(reason: ExitReason) {
-    get_mut(&myself()).exit(reason);
+    if let Ok(mut kernel) = get_mut(&myself()) {
+        kernel.exit(reason);
+    }
 
     // never return, assime that task will not be scheduled
     pending::<()>().await;
@@ -1,16 +1,45 @@
 #![recursion_limit = "256"]
 
+mod agent;
+pub mod dynamic_supervisor;
+mod error;
+pub mod gen_server;
 mod inbox;
+mod info;
 mod kernel;
+mod pg;
 mod pid;
+mod registry;
 mod spawn;
+pub mod supervisor;
+mod task;
+mod timer;
 
-pub use inbox::{Envelope, SaveQueue, __receive, __selective_restore, send, send_exit, send_raw};
-pub use pid::{cpid, myself, MonitorRef, Pid};
+pub use agent::Agent;
+pub use dynamic_supervisor::{DynamicSupervisorOpt, DynamicSupervisorOptBuilder};
+pub use error::{HasturError, SendError};
+pub use gen_server::{CallError, CallResult, Caller, CastResult, GenServer};
+pub use inbox::{
+    __After, __receive, __selective_restore, send, send_raw, Down, Envelope, Exit, SaveQueue,
+};
+pub use info::{process_info, processes, ProcessInfo};
+pub use pg::{broadcast, join, leave, members};
+pub use pid::{cpid, myself, try_myself, MonitorRef, Pid};
+pub use registry::{register, send_named, unregister, whereis, RegistryError};
 pub use spawn::{
-    __spawn, __spawn_link, __spawn_opt, spawn, spawn_link, spawn_opt, SpawnOpt, SpawnOptBuilder,
+    __main, __spawn, __spawn_link, __spawn_opt, run, spawn, spawn_link, spawn_opt, SpawnOpt,
+    SpawnOptBuilder,
+};
+pub use supervisor::{ChildSpec, Restart, Shutdown, Strategy, SupervisorOpt, SupervisorOptBuilder};
+pub use task::{Task, TaskError};
+pub use timer::{
+    cancel_timer, read_timer, send_after, send_interval, send_interval_opt, IntoTimeout, Missed,
+    TimerRef,
 };
 
-pub use kernel::{exit, get_trap_exit, link, trap_exit, ExitReason};
+pub use kernel::{
+    demonitor, exit, get_trap_exit, is_alive, kill, link, monitor, trap_exit, unlink, ExitReason,
+    PanicInfo, Payload,
+};
 
-pub use hastur_macro::receive;
+pub use hastur_macro::{main, receive};
@@ -0,0 +1,79 @@
+use dashmap::DashMap;
+
+use crate::inbox;
+use crate::kernel;
+use crate::pid::Pid;
+
+lazy_static::lazy_static! {
+    static ref PGROUPS: DashMap<String, Vec<Pid>> = {
+        DashMap::new()
+    };
+}
+
+// a process may join a group several times, it stays a member until it
+// leaves as many times as it has joined
+pub fn join<G: Into<String>>(group: G, pid: Pid) -> bool {
+    let group = group.into();
+
+    tracing::trace!(event = "join", ?group, ?pid);
+
+    // holding the kernel keeps the process from leaving its groups meanwhile
+    if let Ok(kernel) = kernel::get(&pid) {
+        kernel.join(group.clone());
+        PGROUPS.entry(group).or_default().push(pid);
+        true
+    } else {
+        false
+    }
+}
+
+pub fn leave(group: &str, pid: Pid) -> bool {
+    tracing::trace!(event = "leave", ?group, ?pid);
+
+    let kernel = kernel::get(&pid).ok();
+
+    let left = if let Some(mut members) = PGROUPS.get_mut(group) {
+        if let Some(position) = members.iter().position(|member| *member == pid) {
+            members.remove(position);
+
+            if !members.contains(&pid) {
+                if let Some(kernel) = &kernel {
+                    kernel.leave(group);
+                }
+            }
+
+            true
+        } else {
+            false
+        }
+    } else {
+        false
+    };
+
+    PGROUPS.remove_if(group, |_, members| members.is_empty());
+
+    left
+}
+
+pub fn members(group: &str) -> Vec<Pid> {
+    PGROUPS
+        .get(group)
+        .map(|members| members.clone())
+        .unwrap_or_default()
+}
+
+pub fn broadcast<T: Clone + Send + 'static>(group: &str, message: T) {
+    for pid in members(group) {
+        let _ = inbox::send(pid, message.clone());
+    }
+}
+
+pub(crate) fn release(pid: &Pid, group: &str) {
+    tracing::trace!(event = "release", ?group, ?pid);
+
+    if let Some(mut members) = PGROUPS.get_mut(group) {
+        members.retain(|member| member != pid);
+    }
+
+    PGROUPS.remove_if(group, |_, members| members.is_empty());
+}
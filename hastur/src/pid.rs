@@ -1,21 +1,33 @@
 use std::cell::Cell;
-use std::sync::atomic::{AtomicU32, Ordering};
 
-static PIDGEN: AtomicU32 = AtomicU32::new(0);
-static MONGEN: AtomicU32 = AtomicU32::new(0);
+use crate::error::HasturError;
+use std::sync::atomic::{AtomicU64, Ordering};
 
+static PIDGEN: AtomicU64 = AtomicU64::new(0);
+static MONGEN: AtomicU64 = AtomicU64::new(0);
+
+// the serial is bumped every time the id wraps around, so a reused id never
+// matches a pid of an earlier process
 #[derive(Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
-pub struct Pid(u32);
+pub struct Pid {
+    serial: u32,
+    id: u32,
+}
 
 impl Pid {
     pub(crate) fn new() -> Self {
-        Self(PIDGEN.fetch_add(1, Ordering::Relaxed))
+        let n = PIDGEN.fetch_add(1, Ordering::Relaxed);
+
+        Self {
+            serial: (n >> u32::BITS) as u32,
+            id: n as u32,
+        }
     }
 }
 
 impl std::fmt::Display for Pid {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        write!(f, "Pid<{}>", self.0)
+        write!(f, "Pid<{}.{}>", self.id, self.serial)
     }
 }
 
@@ -26,15 +38,35 @@ impl std::fmt::Debug for Pid {
 }
 
 thread_local! {
-    pub static PID: Cell<Option<Pid>> = Cell::new(None);
+    pub static PID: Cell<Option<Pid>> = const { Cell::new(None) };
+}
+
+// sets the pid of the thread while a process is polled
+pub(crate) struct Enter(Option<Pid>);
+
+impl Enter {
+    pub(crate) fn new(pid: Pid) -> Self {
+        Self(PID.with(|cell| cell.replace(Some(pid))))
+    }
+}
+
+impl Drop for Enter {
+    fn drop(&mut self) {
+        PID.with(|cell| cell.set(self.0));
+    }
+}
+
+pub fn try_myself() -> Result<Pid, HasturError> {
+    PID.with(|cell| cell.get().ok_or(HasturError::NoProcess))
 }
 
+// panics when called outside of a process, see try_myself
 pub fn myself() -> Pid {
-    PID.with(|cell| cell.get().expect("noproc"))
+    try_myself().unwrap_or_else(|error| panic!("{}", error))
 }
 
 #[derive(Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
-pub struct MonitorRef(u32);
+pub struct MonitorRef(u64);
 
 impl MonitorRef {
     pub(crate) fn new() -> Self {
@@ -54,6 +86,6 @@ impl std::fmt::Debug for MonitorRef {
     }
 }
 
-pub fn cpid() -> u32 {
+pub fn cpid() -> u64 {
     PIDGEN.load(Ordering::Relaxed)
 }
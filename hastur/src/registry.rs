@@ -0,0 +1,89 @@
+use dashmap::{mapref::entry::Entry, DashMap};
+
+use crate::inbox;
+use crate::kernel;
+use crate::pid::Pid;
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum RegistryError {
+    // name is registered by another process
+    Taken(Pid),
+    // process is already registered under another name
+    Registered(String),
+    NoProc(Pid),
+    Unregistered,
+}
+
+impl std::fmt::Display for RegistryError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            RegistryError::Taken(pid) => write!(f, "name is taken by {}", pid),
+            RegistryError::Registered(name) => write!(f, "process is registered as {}", name),
+            RegistryError::NoProc(pid) => write!(f, "noproc {}", pid),
+            RegistryError::Unregistered => f.write_str("name is not registered"),
+        }
+    }
+}
+
+impl std::error::Error for RegistryError {}
+
+lazy_static::lazy_static! {
+    static ref PREGISTRY: DashMap<String, Pid> = {
+        DashMap::new()
+    };
+}
+
+pub fn register<N: Into<String>>(name: N, pid: Pid) -> Result<(), RegistryError> {
+    let name = name.into();
+
+    tracing::trace!(event = "register", ?name, ?pid);
+
+    // holding the kernel keeps the process from releasing its name meanwhile
+    let kernel = kernel::get(&pid).map_err(|_| RegistryError::NoProc(pid))?;
+    let mut registered = kernel.name();
+
+    if let Some(registered) = registered.as_ref() {
+        return Err(RegistryError::Registered(registered.clone()));
+    }
+
+    match PREGISTRY.entry(name.clone()) {
+        Entry::Occupied(entry) => Err(RegistryError::Taken(*entry.get())),
+        Entry::Vacant(entry) => {
+            entry.insert(pid);
+            *registered = Some(name);
+            Ok(())
+        }
+    }
+}
+
+pub fn unregister(name: &str) -> Result<(), RegistryError> {
+    tracing::trace!(event = "unregister", ?name);
+
+    let (_, pid) = PREGISTRY.remove(name).ok_or(RegistryError::Unregistered)?;
+
+    if let Ok(kernel) = kernel::get(&pid) {
+        let mut registered = kernel.name();
+
+        if registered.as_deref() == Some(name) {
+            *registered = None;
+        }
+    }
+
+    Ok(())
+}
+
+pub fn whereis(name: &str) -> Option<Pid> {
+    PREGISTRY.get(name).map(|pid| *pid)
+}
+
+pub fn send_named<T: Send + 'static>(name: &str, message: T) -> Result<(), RegistryError> {
+    let pid = whereis(name).ok_or(RegistryError::Unregistered)?;
+
+    inbox::send(pid, message).map_err(|error| RegistryError::NoProc(error.0))
+}
+
+pub(crate) fn release(pid: &Pid, name: &str) {
+    tracing::trace!(event = "release", ?name, ?pid);
+
+    PREGISTRY.remove_if(name, |_, registered| registered == pid);
+}
@@ -4,16 +4,58 @@ use futures::{
     select_biased, Future,
 };
 
+use std::any::Any;
+use std::cell::RefCell;
 use std::panic::AssertUnwindSafe;
+use std::sync::Once;
 
-use crate::inbox::{self, Exit};
-use crate::kernel::{self, ExitReason};
-use crate::pid::{myself, MonitorRef, Pid, PID};
+use crate::inbox::{self, Down, Exit};
+use crate::kernel::{self, ExitReason, PanicInfo};
+use crate::pid::{myself, Enter, MonitorRef, Pid, PID};
+use crate::{pg, registry, timer};
 
 use derive_builder::Builder;
 
 use tokio::task;
 
+thread_local! {
+    static PANIC_LOCATION: RefCell<Option<String>> = const { RefCell::new(None) };
+}
+
+static PANIC_HOOK: Once = Once::new();
+
+// the unwind payload has only the message, the location is recorded by a hook
+// chained in front of the installed one. a hook set later replaces it, and
+// the location is then unknown.
+fn install_panic_hook() {
+    PANIC_HOOK.call_once(|| {
+        let hook = std::panic::take_hook();
+
+        std::panic::set_hook(Box::new(move |info| {
+            if PID.with(|cell| cell.get()).is_some() {
+                let location = info.location().map(|location| location.to_string());
+                PANIC_LOCATION.with(|cell| *cell.borrow_mut() = location);
+            }
+
+            hook(info);
+        }));
+    });
+}
+
+fn panic_info(payload: Box<dyn Any + Send>) -> PanicInfo {
+    let message = if let Some(message) = payload.downcast_ref::<&str>() {
+        message.to_string()
+    } else if let Some(message) = payload.downcast_ref::<String>() {
+        message.clone()
+    } else {
+        "Box<dyn Any>".to_string()
+    };
+
+    let location = PANIC_LOCATION.with(|cell| cell.borrow_mut().take());
+
+    PanicInfo { message, location }
+}
+
 #[derive(Default, Builder, Debug)]
 pub struct SpawnOpt {
     #[builder(setter(into), default = "false")]
@@ -42,9 +84,6 @@ where
 
         (pid, None, join_handle)
     };
-    if opt.link {
-        kernel::get_mut(&myself()).link(pid);
-    }
 
     (pid, monitor_ref, join_handle)
 }
@@ -92,6 +131,8 @@ fn spawn_int<F>(
 where
     F: Future + Send + 'static,
 {
+    install_panic_hook();
+
     let pid = Pid::new();
 
     let span = tracing::span!(parent: None, tracing::Level::DEBUG, "process", ?pid);
@@ -100,13 +141,24 @@ where
 
     let (self_exit_sender, self_exit_receiver) = oneshot::channel();
 
-    let context = kernel::Kernel::new(pid, self_exit_sender);
+    // under async_metronome processes share the timer wheel of their
+    // spawner, a process spawned from outside of a process starts a new one
+    let spawner = PID
+        .with(|cell| cell.get())
+        .and_then(|parent| kernel::get(&parent).ok());
+    let wheel = timer::wheel(spawner.as_ref().map(|parent| parent.wheel()));
+    drop(spawner);
+
+    let context = kernel::Kernel::new(pid, self_exit_sender, wheel);
 
     if let Some(link_to) = link_to {
         context.link(link_to);
     }
 
     if let Some((monitor_ref, monitor_pid)) = monitor {
+        if let Ok(kernel) = kernel::get(&monitor_pid) {
+            kernel.track_monitor(monitor_ref, pid);
+        }
         context.monitor(monitor_ref, monitor_pid);
     }
 
@@ -118,7 +170,7 @@ where
         let mut future = future.boxed();
 
         let future = poll_fn(move |cx| {
-            PID.with(|cell| cell.set(Some(pid)));
+            let _enter = Enter::new(pid);
             future.as_mut().poll(cx)
         });
 
@@ -138,16 +190,16 @@ where
 
                     let Exit(from, reason) = exit;
 
-                    let trap_exit = kernel::get(&pid).get_trap_exit();
+                    let trap_exit = kernel::get(&pid).is_ok_and(|kernel| kernel.get_trap_exit());
+
+                    if reason == ExitReason::Kill {
+                        tracing::trace!(event=EXIT, ?from, ?reason, trap_exit, outcome = "exit");
+                        break ExitReason::Killed;
+                    }
 
                     if trap_exit {
-                        if reason == ExitReason::Kill {
-                            tracing::trace!(event=EXIT, ?from, ?reason, trap_exit, outcome = "exit");
-                            break reason;
-                        }else{
-                            tracing::trace!(event=EXIT, ?from, ?reason, trap_exit, outcome = "send");
-                            inbox::send(pid, Exit(pid, reason));
-                        }
+                        tracing::trace!(event=EXIT, ?from, ?reason, trap_exit, outcome = "send");
+                        let _ = inbox::send(pid, Exit(from, reason));
                     }else{
                         if reason != ExitReason::Normal {
                             tracing::trace!(event=EXIT, ?from, ?reason, trap_exit, outcome = "exit");
@@ -164,8 +216,8 @@ where
                             Ok(_) => {
                                 ExitReason::Normal
                             },
-                            Err(_) => {
-                                ExitReason::Panic
+                            Err(payload) => {
+                                ExitReason::Panic(panic_info(payload))
                             }
                         };
 
@@ -176,12 +228,55 @@ where
 
         tracing::trace!(event = "exit", ?reason);
 
+        // kill is untrappable only as a signal, peers are notified with killed
+        let reason = if reason == ExitReason::Kill {
+            ExitReason::Killed
+        } else {
+            reason
+        };
+
         inbox::drop(&pid);
-        let context = kernel::remove(&pid);
+        if let Ok(context) = kernel::remove(&pid) {
+            if let Some(name) = context.name().as_deref() {
+                registry::release(&pid, name);
+            }
+
+            context.for_each_group(|group| {
+                pg::release(&pid, group);
+            });
+
+            context.for_each_timer(|timer_ref| {
+                context.wheel().cancel(timer_ref);
+            });
+
+            context.for_each_linked(|linked| {
+                if let Ok(kernel) = kernel::get(linked) {
+                    kernel.unlink(&pid);
+                }
 
-        context.for_each_linked(|linked| {
-            inbox::send_exit(&linked, Exit(pid, reason));
-        });
+                kernel::signal(linked, Exit(pid, reason.clone()));
+            });
+
+            context.for_each_monitoring(|monitor_ref, target| {
+                if let Ok(kernel) = kernel::get(target) {
+                    kernel.demonitor(monitor_ref);
+                }
+            });
+
+            context.for_each_monitor(|monitor_ref, monitor_pid| {
+                tracing::trace!(event = "down", ?monitor_ref, ?monitor_pid, ?reason);
+
+                kernel::notify_monitor(
+                    monitor_pid,
+                    monitor_ref,
+                    Down {
+                        monitor_ref: *monitor_ref,
+                        pid,
+                        reason: reason.clone(),
+                    },
+                );
+            });
+        }
 
         reason
     };
@@ -204,3 +299,22 @@ where
         },
     )
 }
+
+// runs the future as the root process and waits for its exit
+pub async fn run<P>(proc: P) -> ExitReason
+where
+    P: Future + Send + 'static,
+{
+    __spawn(proc).1.await
+}
+
+pub fn __main<P>(proc: P) -> ExitReason
+where
+    P: Future + Send + 'static,
+{
+    tokio::runtime::Builder::new_multi_thread()
+        .enable_all()
+        .build()
+        .expect("runtime")
+        .block_on(run(proc))
+}
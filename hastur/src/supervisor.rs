@@ -0,0 +1,391 @@
+use std::collections::VecDeque;
+use std::sync::Arc;
+use std::time::Duration;
+
+use derive_builder::Builder;
+use futures::future::{BoxFuture, Future, FutureExt};
+
+use crate::gen_server::{self, CallError, CallResult, Caller, CastResult, GenServer, CALL_TIMEOUT};
+use crate::inbox::{self, Down, Envelope, Exit};
+use crate::kernel::{self, ExitReason};
+use crate::pid::{myself, Pid};
+use crate::timer;
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Restart {
+    // always restarted
+    Permanent,
+    // restarted unless it exits with normal or shutdown
+    Transient,
+    // never restarted
+    Temporary,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Shutdown {
+    BrutalKill,
+    // time to exit after a shutdown signal before the child is killed
+    Timeout(Duration),
+}
+
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub enum Strategy {
+    #[default]
+    OneForOne,
+    OneForAll,
+    RestForOne,
+}
+
+type Start = Arc<dyn Fn() -> BoxFuture<'static, Result<Pid, ExitReason>> + Send + Sync>;
+
+// the start function runs in the supervisor and must link the child to it,
+// e.g. with spawn_link or gen_server::start_link
+#[derive(Clone)]
+pub struct ChildSpec {
+    id: String,
+    start: Start,
+    restart: Restart,
+    pub(crate) shutdown: Shutdown,
+}
+
+impl ChildSpec {
+    pub fn new<I, F, S>(id: I, start: F) -> Self
+    where
+        I: Into<String>,
+        F: Fn() -> S + Send + Sync + 'static,
+        S: Future<Output = Result<Pid, ExitReason>> + Send + 'static,
+    {
+        Self {
+            id: id.into(),
+            start: Arc::new(move || start().boxed()),
+            restart: Restart::Permanent,
+            shutdown: Shutdown::Timeout(Duration::from_secs(5)),
+        }
+    }
+
+    pub fn restart(mut self, restart: Restart) -> Self {
+        self.restart = restart;
+        self
+    }
+
+    pub fn shutdown(mut self, shutdown: Shutdown) -> Self {
+        self.shutdown = shutdown;
+        self
+    }
+
+    pub fn id(&self) -> &str {
+        &self.id
+    }
+
+    pub(crate) async fn start(&self) -> Result<Pid, ExitReason> {
+        (self.start)().await
+    }
+
+    pub(crate) fn restart_on(&self, reason: &ExitReason) -> bool {
+        match self.restart {
+            Restart::Permanent => true,
+            Restart::Transient => !matches!(
+                reason,
+                ExitReason::Normal | ExitReason::Shutdown | ExitReason::ShutdownWith(_)
+            ),
+            Restart::Temporary => false,
+        }
+    }
+}
+
+impl std::fmt::Debug for ChildSpec {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("ChildSpec")
+            .field("id", &self.id)
+            .field("restart", &self.restart)
+            .field("shutdown", &self.shutdown)
+            .finish()
+    }
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct Child {
+    pub id: String,
+    pub pid: Option<Pid>,
+}
+
+#[derive(Clone, Builder, Debug)]
+pub struct SupervisorOpt {
+    #[builder(default)]
+    strategy: Strategy,
+    // restarts allowed within the period before the supervisor gives up
+    #[builder(default = "3")]
+    intensity: usize,
+    #[builder(default = "Duration::from_secs(5)")]
+    period: Duration,
+}
+
+impl Default for SupervisorOpt {
+    fn default() -> Self {
+        SupervisorOptBuilder::default().build().unwrap()
+    }
+}
+
+// counts restarts within the period, in ticks of the timer clock
+pub(crate) struct Intensity {
+    intensity: usize,
+    period: u64,
+    restarts: VecDeque<u64>,
+}
+
+impl Intensity {
+    pub(crate) fn new(intensity: usize, period: Duration) -> Self {
+        Self {
+            intensity,
+            period: timer::ticks(period),
+            restarts: VecDeque::new(),
+        }
+    }
+
+    // false when the restart exceeds the intensity
+    pub(crate) fn restart(&mut self) -> bool {
+        let now = timer::now();
+
+        while let Some(restart) = self.restarts.front() {
+            if now - restart > self.period {
+                self.restarts.pop_front();
+            } else {
+                break;
+            }
+        }
+
+        self.restarts.push_back(now);
+        self.restarts.len() <= self.intensity
+    }
+}
+
+// stops a linked child and waits until it is gone
+pub(crate) async fn terminate_child(pid: Pid, shutdown: Shutdown) {
+    tracing::trace!(event = "terminate_child", ?pid, ?shutdown);
+
+    let monitor_ref = kernel::monitor(pid);
+
+    stop(pid, shutdown, |envelope| {
+        envelope
+            .downcast_ref::<Down>()
+            .is_some_and(|down| down.monitor_ref == monitor_ref)
+    })
+    .await;
+}
+
+// stops a linked process the caller monitors, returns the first message
+// matching f: its down or, for a task, its reply
+pub(crate) async fn stop<F: Fn(&Envelope) -> bool>(pid: Pid, shutdown: Shutdown, f: F) -> Envelope {
+    kernel::unlink(pid);
+
+    let envelope = match shutdown {
+        Shutdown::BrutalKill => {
+            kernel::kill(pid, ExitReason::Kill);
+            inbox::receive_match(&f, None).await
+        }
+        Shutdown::Timeout(timeout) => {
+            kernel::kill(pid, ExitReason::Shutdown);
+
+            match inbox::receive_match(&f, Some(timeout)).await {
+                Some(envelope) => Some(envelope),
+                None => {
+                    kernel::kill(pid, ExitReason::Kill);
+                    inbox::receive_match(&f, None).await
+                }
+            }
+        }
+    };
+
+    // an exit sent before unlink is already in the mailbox once down arrived
+    inbox::flush(&myself(), |envelope| {
+        envelope
+            .downcast_ref::<Exit>()
+            .is_some_and(|exit| exit.0 == pid)
+    });
+
+    // without a timeout the wait ends with the down at the latest
+    envelope.unwrap()
+}
+
+struct Running {
+    spec: ChildSpec,
+    pid: Option<Pid>,
+}
+
+pub struct Supervisor {
+    strategy: Strategy,
+    intensity: Intensity,
+    children: Vec<Running>,
+}
+
+pub enum SupervisorCall {
+    WhichChildren,
+}
+
+impl Supervisor {
+    // starts the children from index on, stops at the first failure
+    async fn start_children(&mut self, from: usize) -> Result<(), ExitReason> {
+        for running in &mut self.children[from..] {
+            match running.spec.start().await {
+                Ok(pid) => running.pid = Some(pid),
+                Err(reason) => {
+                    tracing::trace!(event = "start_error", id = running.spec.id, ?reason);
+                    return Err(reason);
+                }
+            }
+        }
+
+        Ok(())
+    }
+
+    // terminates the children from index on, in reverse start order
+    async fn terminate_children(&mut self, from: usize) {
+        for running in self.children[from..].iter_mut().rev() {
+            if let Some(pid) = running.pid.take() {
+                terminate_child(pid, running.spec.shutdown).await;
+            }
+        }
+    }
+
+    async fn restart(&mut self, index: usize) -> Result<(), ExitReason> {
+        let from = match self.strategy {
+            Strategy::OneForOne => index,
+            Strategy::OneForAll => 0,
+            Strategy::RestForOne => index,
+        };
+
+        loop {
+            if !self.intensity.restart() {
+                tracing::trace!(event = "shutdown", outcome = "intensity");
+                return Err(ExitReason::Shutdown);
+            }
+
+            match self.strategy {
+                Strategy::OneForOne => {
+                    let running = &mut self.children[index];
+
+                    match running.spec.start().await {
+                        Ok(pid) => {
+                            running.pid = Some(pid);
+                            return Ok(());
+                        }
+                        Err(_) => continue,
+                    }
+                }
+                Strategy::OneForAll | Strategy::RestForOne => {
+                    self.terminate_children(from).await;
+
+                    // temporary children are stopped with their siblings but
+                    // never restarted
+                    let rest = self.children.split_off(from);
+                    self.children.extend(
+                        rest.into_iter()
+                            .filter(|running| running.spec.restart != Restart::Temporary),
+                    );
+
+                    match self.start_children(from).await {
+                        Ok(()) => return Ok(()),
+                        Err(_) => continue,
+                    }
+                }
+            }
+        }
+    }
+}
+
+impl GenServer for Supervisor {
+    type Args = (SupervisorOpt, Vec<ChildSpec>);
+    type Call = SupervisorCall;
+    type Reply = Vec<Child>;
+    type Cast = ();
+
+    async fn init((opt, children): Self::Args) -> Result<Self, ExitReason> {
+        kernel::trap_exit(true);
+
+        let mut supervisor = Supervisor {
+            strategy: opt.strategy,
+            intensity: Intensity::new(opt.intensity, opt.period),
+            children: children
+                .into_iter()
+                .map(|spec| Running { spec, pid: None })
+                .collect(),
+        };
+
+        if let Err(reason) = supervisor.start_children(0).await {
+            supervisor.terminate_children(0).await;
+            return Err(reason);
+        }
+
+        Ok(supervisor)
+    }
+
+    async fn handle_call(&mut self, request: SupervisorCall, _: Caller) -> CallResult<Vec<Child>> {
+        match request {
+            SupervisorCall::WhichChildren => CallResult::Reply(
+                self.children
+                    .iter()
+                    .map(|running| Child {
+                        id: running.spec.id.clone(),
+                        pid: running.pid,
+                    })
+                    .collect(),
+            ),
+        }
+    }
+
+    async fn handle_cast(&mut self, _: ()) -> CastResult {
+        CastResult::NoReply
+    }
+
+    async fn handle_info(&mut self, message: Envelope) -> CastResult {
+        let Some(Exit(pid, reason)) = message.downcast::<Exit>() else {
+            return CastResult::NoReply;
+        };
+
+        let Some(index) = self
+            .children
+            .iter()
+            .position(|running| running.pid == Some(pid))
+        else {
+            return CastResult::NoReply;
+        };
+
+        tracing::trace!(
+            event = "child_exit",
+            id = self.children[index].spec.id,
+            ?pid,
+            ?reason
+        );
+
+        self.children[index].pid = None;
+
+        if !self.children[index].spec.restart_on(&reason) {
+            if self.children[index].spec.restart == Restart::Temporary {
+                self.children.remove(index);
+            }
+
+            return CastResult::NoReply;
+        }
+
+        match self.restart(index).await {
+            Ok(()) => CastResult::NoReply,
+            Err(reason) => CastResult::Stop(reason),
+        }
+    }
+
+    async fn terminate(&mut self, _: ExitReason) {
+        self.terminate_children(0).await;
+    }
+}
+
+pub async fn start(opt: SupervisorOpt, children: Vec<ChildSpec>) -> Result<Pid, ExitReason> {
+    gen_server::start::<Supervisor>((opt, children)).await
+}
+
+pub async fn start_link(opt: SupervisorOpt, children: Vec<ChildSpec>) -> Result<Pid, ExitReason> {
+    gen_server::start_link::<Supervisor>((opt, children)).await
+}
+
+pub async fn which_children(supervisor: Pid) -> Result<Vec<Child>, CallError> {
+    gen_server::call::<Supervisor>(supervisor, SupervisorCall::WhichChildren, CALL_TIMEOUT).await
+}
@@ -0,0 +1,171 @@
+use std::marker::PhantomData;
+use std::time::Duration;
+
+use futures::{future::FutureExt, select_biased, Future};
+
+use crate::inbox::{self, Down, Envelope, SaveQueue};
+use crate::kernel::{self, ExitReason};
+use crate::pid::{myself, MonitorRef, Pid};
+use crate::spawn::{self, SpawnOptBuilder};
+use crate::supervisor::{self, Shutdown};
+use crate::timer;
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum TaskError {
+    // the task exited before replying
+    Exit(ExitReason),
+    Timeout,
+}
+
+impl std::fmt::Display for TaskError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            TaskError::Exit(reason) => write!(f, "task exited: {:?}", reason),
+            TaskError::Timeout => f.write_str("timeout"),
+        }
+    }
+}
+
+impl std::error::Error for TaskError {}
+
+// the reply is tagged with the task pid, it is unique thanks to the serial
+struct TaskReply<T> {
+    pid: Pid,
+    value: T,
+}
+
+// a process linked to and monitored by its owner, the output of the future is
+// sent to the owner
+#[derive(Debug)]
+pub struct Task<T> {
+    pid: Pid,
+    monitor_ref: MonitorRef,
+    _output: PhantomData<fn() -> T>,
+}
+
+impl<T: Send + 'static> Task<T> {
+    pub fn spawn<F>(future: F) -> Self
+    where
+        F: Future<Output = T> + Send + 'static,
+    {
+        let owner = myself();
+
+        let (pid, monitor_ref, _) = spawn::__spawn_opt(
+            async move {
+                let value = future.await;
+                let _ = inbox::send(
+                    owner,
+                    TaskReply {
+                        pid: myself(),
+                        value,
+                    },
+                );
+            },
+            SpawnOptBuilder::default()
+                .link(true)
+                .monitor(true)
+                .build()
+                .unwrap(),
+        );
+
+        Task {
+            pid,
+            monitor_ref: monitor_ref.unwrap(),
+            _output: PhantomData,
+        }
+    }
+
+    pub fn pid(&self) -> Pid {
+        self.pid
+    }
+
+    fn matches(&self, envelope: &Envelope) -> bool {
+        envelope
+            .downcast_ref::<TaskReply<T>>()
+            .is_some_and(|reply| reply.pid == self.pid)
+            || envelope
+                .downcast_ref::<Down>()
+                .is_some_and(|down| down.monitor_ref == self.monitor_ref)
+    }
+
+    // the envelope is either the reply or the down of this task
+    fn result(&self, envelope: Envelope) -> Result<T, ExitReason> {
+        if envelope.is::<TaskReply<T>>() {
+            // a down is in the mailbox only if the task exited before demonitor
+            if !kernel::demonitor(self.monitor_ref, false) {
+                let monitor_ref = self.monitor_ref;
+                inbox::flush(&myself(), |envelope| {
+                    envelope
+                        .downcast_ref::<Down>()
+                        .is_some_and(|down| down.monitor_ref == monitor_ref)
+                });
+            }
+
+            Ok(envelope.downcast::<TaskReply<T>>().unwrap().value)
+        } else {
+            Err(envelope.downcast::<Down>().unwrap().reason)
+        }
+    }
+
+    // on timeout the task is killed
+    pub async fn await_timeout(self, timeout: Duration) -> Result<T, TaskError> {
+        match inbox::receive_match(|envelope| self.matches(envelope), Some(timeout)).await {
+            Some(envelope) => self.result(envelope).map_err(TaskError::Exit),
+            None => {
+                self.shutdown(Shutdown::BrutalKill).await;
+                Err(TaskError::Timeout)
+            }
+        }
+    }
+
+    // waits for all tasks up to the timeout, tasks without a result are left running
+    pub async fn yield_many(
+        tasks: Vec<Task<T>>,
+        timeout: Duration,
+    ) -> Vec<(Task<T>, Option<Result<T, ExitReason>>)> {
+        let mut results: Vec<_> = tasks.into_iter().map(|task| (task, None)).collect();
+        let mut pending = results.len();
+
+        let mut save_queue = SaveQueue::new();
+        let mut timeout = timer::sleep(timeout).fuse();
+
+        while pending > 0 {
+            select_biased! {
+                envelope = inbox::__receive().fuse() => {
+                    let found = results
+                        .iter()
+                        .position(|(task, result)| result.is_none() && task.matches(&envelope));
+
+                    match found {
+                        Some(index) => {
+                            let result = results[index].0.result(envelope);
+                            results[index].1 = Some(result);
+                            pending -= 1;
+                        }
+                        None => save_queue.push_front(envelope),
+                    }
+                },
+
+                _ = timeout => {
+                    break;
+                }
+            }
+        }
+
+        inbox::__selective_restore(save_queue);
+
+        results
+    }
+
+    // stops the task, returns its result if it completed in the meantime
+    pub async fn shutdown(self, shutdown: Shutdown) -> Option<Result<T, ExitReason>> {
+        let envelope =
+            supervisor::stop(self.pid, shutdown, |envelope| self.matches(envelope)).await;
+
+        match self.result(envelope) {
+            Ok(value) => Some(Ok(value)),
+            Err(ExitReason::Shutdown | ExitReason::Killed) => None,
+            Err(reason) => Some(Err(reason)),
+        }
+    }
+}
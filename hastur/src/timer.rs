@@ -0,0 +1,421 @@
+use std::collections::{BTreeMap, HashMap};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+use futures::{
+    future::{BoxFuture, FutureExt},
+    select_biased,
+};
+use tokio::sync::Notify;
+
+use crate::inbox;
+use crate::kernel;
+use crate::pid::{myself, Pid};
+
+static TIMERGEN: AtomicU64 = AtomicU64::new(0);
+
+// under async_metronome the clock is virtual, it advances one tick per
+// millisecond and ticks advance only when every task is pending
+const TICK: Duration = Duration::from_millis(1);
+
+lazy_static::lazy_static! {
+    static ref EPOCH: tokio::time::Instant = tokio::time::Instant::now();
+
+    // the wheel of every process outside of async_metronome. its driver runs
+    // on a runtime of its own, it outlives the runtimes of the processes.
+    static ref WHEEL: Arc<Wheel> = Arc::default();
+    static ref RUNTIME: tokio::runtime::Runtime = tokio::runtime::Builder::new_multi_thread()
+        .worker_threads(1)
+        .thread_name("hastur-timer")
+        .enable_time()
+        .build()
+        .unwrap();
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, Hash)]
+pub struct TimerRef {
+    owner: Pid,
+    id: u64,
+}
+
+impl TimerRef {
+    fn new(owner: Pid) -> Self {
+        Self {
+            owner,
+            id: TIMERGEN.fetch_add(1, Ordering::Relaxed),
+        }
+    }
+}
+
+impl std::fmt::Display for TimerRef {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "TimerRef<{}>", self.id)
+    }
+}
+
+impl std::fmt::Debug for TimerRef {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(self.to_string().as_str())
+    }
+}
+
+// current time in ticks. the virtual tick is read as assert_tick! does, with
+// a hidden function of async_metronome, hence the exact version in Cargo.toml
+pub(crate) fn now() -> u64 {
+    if async_metronome::is_context() {
+        async_metronome::__private_get_tick() as u64
+    } else {
+        EPOCH.elapsed().as_millis() as u64
+    }
+}
+
+pub(crate) fn ticks(duration: Duration) -> u64 {
+    duration.as_nanos().div_ceil(TICK.as_nanos()) as u64
+}
+
+fn duration(ticks: u64) -> Duration {
+    Duration::from_nanos(ticks * TICK.as_nanos() as u64)
+}
+
+fn remaining(deadline: u64) -> Duration {
+    duration(deadline.saturating_sub(now()))
+}
+
+fn sleep_until(tick: u64) -> BoxFuture<'static, ()> {
+    if async_metronome::is_context() {
+        async move {
+            async_metronome::await_tick!(tick);
+        }
+        .boxed()
+    } else {
+        tokio::time::sleep_until(*EPOCH + duration(tick)).boxed()
+    }
+}
+
+pub(crate) fn sleep(duration: Duration) -> BoxFuture<'static, ()> {
+    if duration.is_zero() {
+        futures::future::ready(()).boxed()
+    } else {
+        sleep_until(now() + ticks(duration))
+    }
+}
+
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub enum Missed {
+    // one message for the ticks missed by a late timer
+    #[default]
+    Skip,
+    // one message for every missed tick
+    Burst,
+}
+
+// the timeout of receive! after, integers are milliseconds as in erlang
+pub trait IntoTimeout {
+    fn into_timeout(self) -> Duration;
+}
+
+impl IntoTimeout for Duration {
+    fn into_timeout(self) -> Duration {
+        self
+    }
+}
+
+impl IntoTimeout for u64 {
+    fn into_timeout(self) -> Duration {
+        Duration::from_millis(self)
+    }
+}
+
+macro_rules! into_timeout {
+    ($($int:ty),*) => {
+        $(
+            impl IntoTimeout for $int {
+                fn into_timeout(self) -> Duration {
+                    Duration::from_millis(u64::try_from(self).expect("negative timeout"))
+                }
+            }
+        )*
+    };
+}
+
+into_timeout!(u32, usize, i32, i64);
+
+struct Timer {
+    deadline: u64,
+    // called with the deadline and the current time, a periodic timer
+    // returns its next deadline
+    action: Box<dyn FnMut(u64, u64) -> Option<u64> + Send>,
+}
+
+#[derive(Default)]
+struct State {
+    // timers by deadline, one slot per tick
+    slots: BTreeMap<u64, Vec<TimerRef>>,
+    timers: HashMap<TimerRef, Timer>,
+    running: bool,
+}
+
+// timers driven by a single task that runs while there are pending timers.
+// under async_metronome every test case gets its own wheel, the clock of one
+// test case means nothing to another.
+#[derive(Default)]
+pub(crate) struct Wheel {
+    state: Mutex<State>,
+    notify: Notify,
+}
+
+impl Wheel {
+    fn insert(self: &Arc<Self>, timer_ref: TimerRef, timer: Timer) {
+        let mut state = self.state.lock().unwrap();
+
+        let earliest = state.slots.keys().next().copied();
+        let deadline = timer.deadline;
+
+        state.slots.entry(deadline).or_default().push(timer_ref);
+        state.timers.insert(timer_ref, timer);
+
+        if !state.running {
+            state.running = true;
+
+            let task = drive(self.clone());
+            if async_metronome::is_context() {
+                drop(async_metronome::spawn(task));
+            } else {
+                RUNTIME.spawn(task);
+            }
+        } else if earliest.is_none_or(|earliest| deadline < earliest) {
+            self.notify.notify_one();
+        }
+    }
+
+    // returns the deadline of the cancelled timer
+    pub(crate) fn cancel(&self, timer_ref: &TimerRef) -> Option<u64> {
+        let mut state = self.state.lock().unwrap();
+
+        let timer = state.timers.remove(timer_ref)?;
+
+        if let Some(slot) = state.slots.get_mut(&timer.deadline) {
+            slot.retain(|pending| pending != timer_ref);
+
+            if slot.is_empty() {
+                state.slots.remove(&timer.deadline);
+                self.notify.notify_one();
+            }
+        }
+
+        Some(timer.deadline)
+    }
+
+    // a periodic timer is put back unless its owner cancelled it while it fired
+    fn rearm(&self, timer_ref: TimerRef, timer: Timer) -> bool {
+        let mut state = self.state.lock().unwrap();
+
+        let tracked =
+            kernel::get(&timer_ref.owner).is_ok_and(|kernel| kernel.tracks_timer(&timer_ref));
+
+        if tracked {
+            state
+                .slots
+                .entry(timer.deadline)
+                .or_default()
+                .push(timer_ref);
+            state.timers.insert(timer_ref, timer);
+        }
+
+        tracked
+    }
+
+    fn read(&self, timer_ref: &TimerRef) -> Option<u64> {
+        let state = self.state.lock().unwrap();
+
+        state.timers.get(timer_ref).map(|timer| timer.deadline)
+    }
+
+    fn expire(&self, now: u64) -> Vec<(TimerRef, Timer)> {
+        let mut state = self.state.lock().unwrap();
+
+        let mut expired = Vec::new();
+
+        while let Some(slot) = state.slots.first_entry() {
+            if *slot.key() > now {
+                break;
+            }
+
+            for timer_ref in slot.remove() {
+                if let Some(timer) = state.timers.remove(&timer_ref) {
+                    expired.push((timer_ref, timer));
+                }
+            }
+        }
+
+        expired
+    }
+
+    // the driver stops when nothing is left
+    fn next(&self) -> Option<u64> {
+        let mut state = self.state.lock().unwrap();
+
+        let next = state.slots.keys().next().copied();
+
+        if next.is_none() {
+            state.running = false;
+        }
+
+        next
+    }
+}
+
+// the wheel of a new process: the wheel of its spawner under async_metronome,
+// the global wheel otherwise
+pub(crate) fn wheel(spawner: Option<&Arc<Wheel>>) -> Arc<Wheel> {
+    if async_metronome::is_context() {
+        spawner.cloned().unwrap_or_default()
+    } else {
+        WHEEL.clone()
+    }
+}
+
+async fn drive(wheel: Arc<Wheel>) {
+    loop {
+        let now = now();
+
+        for (timer_ref, mut timer) in wheel.expire(now) {
+            tracing::trace!(event = "timer", ?timer_ref);
+
+            let rearmed = match (timer.action)(timer.deadline, now) {
+                Some(deadline) => {
+                    timer.deadline = deadline;
+                    wheel.rearm(timer_ref, timer)
+                }
+                None => false,
+            };
+
+            if !rearmed {
+                if let Ok(kernel) = kernel::get(&timer_ref.owner) {
+                    kernel.untrack_timer(&timer_ref);
+                }
+            }
+        }
+
+        let Some(next) = wheel.next() else {
+            return;
+        };
+
+        // an earlier timer or a cancel recomputes the next deadline
+        select_biased! {
+            _ = sleep_until(next).fuse() => {},
+            _ = wheel.notify.notified().fuse() => {},
+        }
+    }
+}
+
+// timers are owned by the calling process and cancelled when it exits
+fn start_timer(
+    duration: Duration,
+    timer_ref: TimerRef,
+    action: Box<dyn FnMut(u64, u64) -> Option<u64> + Send>,
+) {
+    // the kernel is released before the wheel is locked, rearm locks them
+    // in the opposite order
+    let wheel = kernel::get(&timer_ref.owner).map(|kernel| {
+        kernel.track_timer(timer_ref);
+        kernel.wheel().clone()
+    });
+
+    if let Ok(wheel) = wheel {
+        wheel.insert(
+            timer_ref,
+            Timer {
+                deadline: now() + ticks(duration),
+                action,
+            },
+        );
+    }
+}
+
+pub fn send_after<T: Send + 'static>(duration: Duration, to: Pid, message: T) -> TimerRef {
+    let timer_ref = TimerRef::new(myself());
+
+    tracing::trace!(event = "send_after", ?timer_ref, ?duration, ?to);
+
+    let mut message = Some(message);
+    start_timer(
+        duration,
+        timer_ref,
+        Box::new(move |_, _| {
+            if let Some(message) = message.take() {
+                let _ = inbox::send(to, message);
+            }
+            None
+        }),
+    );
+
+    timer_ref
+}
+
+// the factory runs on the timer thread, a slow factory delays every timer
+pub fn send_interval<T, F>(period: Duration, to: Pid, factory: F) -> TimerRef
+where
+    T: Send + 'static,
+    F: FnMut() -> T + Send + 'static,
+{
+    send_interval_opt(period, to, factory, Missed::default())
+}
+
+// stops at the first tick after the target exits. the factory runs on the
+// timer thread, as for send_interval.
+pub fn send_interval_opt<T, F>(
+    period: Duration,
+    to: Pid,
+    mut factory: F,
+    missed: Missed,
+) -> TimerRef
+where
+    T: Send + 'static,
+    F: FnMut() -> T + Send + 'static,
+{
+    let timer_ref = TimerRef::new(myself());
+    let period = ticks(period).max(1);
+
+    tracing::trace!(event = "send_interval", ?timer_ref, period, ?to, ?missed);
+
+    start_timer(
+        duration(period),
+        timer_ref,
+        Box::new(move |deadline, now| {
+            let late = now.saturating_sub(deadline) / period;
+
+            let count = match missed {
+                Missed::Skip => 1,
+                Missed::Burst => late + 1,
+            };
+
+            for _ in 0..count {
+                inbox::send(to, factory()).ok()?;
+            }
+
+            Some(deadline + (late + 1) * period)
+        }),
+    );
+
+    timer_ref
+}
+
+// returns the time left, none if the timer has fired or was cancelled
+pub fn cancel_timer(timer_ref: TimerRef) -> Option<Duration> {
+    let wheel = kernel::get(&timer_ref.owner).ok().map(|kernel| {
+        kernel.untrack_timer(&timer_ref);
+        kernel.wheel().clone()
+    })?;
+
+    wheel.cancel(&timer_ref).map(remaining)
+}
+
+pub fn read_timer(timer_ref: TimerRef) -> Option<Duration> {
+    let wheel = kernel::get(&timer_ref.owner)
+        .ok()
+        .map(|kernel| kernel.wheel().clone())?;
+
+    wheel.read(&timer_ref).map(remaining)
+}
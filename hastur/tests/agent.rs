@@ -0,0 +1,67 @@
+#[cfg(test)]
+mod process_tests {
+    use std::collections::HashMap;
+
+    use hastur::*;
+
+    #[async_metronome::test]
+    async fn get_update() {
+        let (_, handle) = __spawn(async {
+            let agent = Agent::start(HashMap::<String, u32>::new).await.unwrap();
+
+            agent
+                .update(|cache| {
+                    cache.insert("a".to_string(), 1);
+                })
+                .await
+                .unwrap();
+            agent.cast(|cache| {
+                cache.insert("b".to_string(), 2);
+            });
+
+            assert_eq!(agent.get(|cache| cache.len()).await, Ok(2));
+            assert_eq!(
+                agent.get_and_update(|cache| cache.remove("a")).await,
+                Ok(Some(1))
+            );
+            assert_eq!(agent.get(|cache| cache.get("a").copied()).await, Ok(None));
+
+            let monitor_ref = monitor(agent.pid());
+            assert_eq!(agent.stop().await, Ok(()));
+
+            let down = __receive().await.downcast::<Down>().unwrap();
+            assert_eq!(down.monitor_ref, monitor_ref);
+            assert_eq!(down.reason, ExitReason::Normal);
+
+            assert_eq!(
+                agent.get(|cache| cache.len()).await,
+                Err(CallError::Exit(ExitReason::NoProc(agent.pid())))
+            );
+        });
+
+        assert_eq!(handle.await, ExitReason::Normal);
+    }
+
+    #[async_metronome::test]
+    async fn serialized() {
+        let (_, handle) = __spawn(async {
+            let agent = Agent::start(|| 0u32).await.unwrap();
+
+            let tasks: Vec<_> = (0..10)
+                .map(|_| Task::spawn(async move { agent.update(|n| *n += 1).await }))
+                .collect();
+
+            for task in tasks {
+                task.await_timeout(std::time::Duration::from_millis(100))
+                    .await
+                    .unwrap()
+                    .unwrap();
+            }
+
+            assert_eq!(agent.get(|n| *n).await, Ok(10));
+            agent.stop().await.unwrap();
+        });
+
+        assert_eq!(handle.await, ExitReason::Normal);
+    }
+}
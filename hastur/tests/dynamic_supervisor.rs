@@ -0,0 +1,125 @@
+#[cfg(test)]
+mod process_tests {
+    use hastur::dynamic_supervisor::{self, ChildError};
+    use hastur::*;
+
+    fn worker(id: &str) -> ChildSpec {
+        ChildSpec::new(id, || async {
+            Ok(spawn_link(async { while __receive().await != "stop" {} }))
+        })
+    }
+
+    async fn children(sup: Pid) -> Vec<Pid> {
+        dynamic_supervisor::which_children(sup)
+            .await
+            .unwrap()
+            .into_iter()
+            .filter_map(|child| child.pid)
+            .collect()
+    }
+
+    async fn stop(sup: Pid) -> ExitReason {
+        let monitor_ref = monitor(sup);
+        kill(sup, ExitReason::Shutdown);
+
+        let down = __receive().await.downcast::<Down>().unwrap();
+        assert_eq!(down.monitor_ref, monitor_ref);
+        down.reason
+    }
+
+    #[async_metronome::test]
+    async fn start_terminate() {
+        let (_, handle) = __spawn(async {
+            let sup = dynamic_supervisor::start(DynamicSupervisorOpt::default())
+                .await
+                .unwrap();
+            assert!(children(sup).await.is_empty());
+
+            let a = dynamic_supervisor::start_child(sup, worker("a"))
+                .await
+                .unwrap();
+            let b = dynamic_supervisor::start_child(sup, worker("b"))
+                .await
+                .unwrap();
+            assert_eq!(children(sup).await, vec![a, b]);
+
+            assert_eq!(dynamic_supervisor::terminate_child(sup, a).await, Ok(()));
+            assert!(!is_alive(a));
+            assert_eq!(children(sup).await, vec![b]);
+            assert_eq!(
+                dynamic_supervisor::terminate_child(sup, a).await,
+                Err(ChildError::NotFound(a))
+            );
+
+            assert_eq!(stop(sup).await, ExitReason::Shutdown);
+            assert!(!is_alive(b));
+        });
+
+        assert_eq!(handle.await, ExitReason::Normal);
+    }
+
+    #[async_metronome::test]
+    async fn max_children() {
+        let (_, handle) = __spawn(async {
+            let opt = DynamicSupervisorOptBuilder::default()
+                .max_children(1)
+                .build()
+                .unwrap();
+            let sup = dynamic_supervisor::start(opt).await.unwrap();
+
+            let a = dynamic_supervisor::start_child(sup, worker("a"))
+                .await
+                .unwrap();
+            assert_eq!(
+                dynamic_supervisor::start_child(sup, worker("b")).await,
+                Err(ChildError::MaxChildren)
+            );
+
+            let failing = ChildSpec::new("failing", || async { Err(ExitReason::custom("init")) });
+            dynamic_supervisor::terminate_child(sup, a).await.unwrap();
+            assert_eq!(
+                dynamic_supervisor::start_child(sup, failing).await,
+                Err(ChildError::Start(ExitReason::custom("init")))
+            );
+
+            assert_eq!(stop(sup).await, ExitReason::Shutdown);
+        });
+
+        assert_eq!(handle.await, ExitReason::Normal);
+    }
+
+    #[async_metronome::test]
+    async fn restart() {
+        let (_, handle) = __spawn(async {
+            let sup = dynamic_supervisor::start(DynamicSupervisorOpt::default())
+                .await
+                .unwrap();
+
+            let permanent = dynamic_supervisor::start_child(sup, worker("permanent"))
+                .await
+                .unwrap();
+            let temporary = dynamic_supervisor::start_child(
+                sup,
+                worker("temporary").restart(Restart::Temporary),
+            )
+            .await
+            .unwrap();
+
+            for pid in [permanent, temporary] {
+                let monitor_ref = monitor(pid);
+                kill(pid, ExitReason::custom("crash"));
+
+                let down = __receive().await.downcast::<Down>().unwrap();
+                assert_eq!(down.monitor_ref, monitor_ref);
+            }
+
+            let pids = children(sup).await;
+            assert_eq!(pids.len(), 1);
+            assert_ne!(pids[0], permanent);
+
+            assert_eq!(stop(sup).await, ExitReason::Shutdown);
+        });
+
+        assert_eq!(handle.await, ExitReason::Normal);
+    }
+}
@@ -0,0 +1,292 @@
+#[cfg(test)]
+mod process_tests {
+    use std::time::Duration;
+
+    use hastur::*;
+
+    const TIMEOUT: Duration = Duration::from_millis(100);
+
+    struct Counter(u32);
+
+    #[derive(Debug, PartialEq)]
+    enum Request {
+        Get,
+        Add(u32),
+        Hang,
+        // replies at the given tick
+        Slow(usize),
+        // replies at the given tick with the save queue length of the caller
+        Saved(usize),
+        Crash,
+        Stop,
+    }
+
+    impl GenServer for Counter {
+        type Args = u32;
+        type Call = Request;
+        type Reply = u32;
+        type Cast = u32;
+
+        async fn init(args: u32) -> Result<Self, ExitReason> {
+            if args == 0 {
+                Err(ExitReason::custom("zero"))
+            } else {
+                Ok(Counter(args))
+            }
+        }
+
+        async fn handle_call(&mut self, request: Request, caller: Caller) -> CallResult<u32> {
+            match request {
+                Request::Get => CallResult::Reply(self.0),
+                Request::Add(n) => {
+                    self.0 += n;
+                    CallResult::Reply(self.0)
+                }
+                Request::Hang => CallResult::NoReply,
+                Request::Slow(tick) => {
+                    async_metronome::await_tick!(tick);
+                    CallResult::Reply(self.0)
+                }
+                Request::Saved(tick) => {
+                    async_metronome::await_tick!(tick);
+                    let info = process_info(caller.pid()).unwrap();
+                    CallResult::Reply(info.save_queue_len as u32)
+                }
+                Request::Crash => panic!("crash"),
+                Request::Stop => CallResult::Stop(ExitReason::Shutdown, self.0),
+            }
+        }
+
+        async fn handle_cast(&mut self, n: u32) -> CastResult {
+            self.0 += n;
+            CastResult::NoReply
+        }
+
+        async fn handle_info(&mut self, message: Envelope) -> CastResult {
+            if message == "stop" {
+                CastResult::Stop(ExitReason::shutdown("info"))
+            } else {
+                CastResult::NoReply
+            }
+        }
+    }
+
+    #[async_metronome::test]
+    async fn call_cast() {
+        let (_, handle) = __spawn(async {
+            let pid = gen_server::start::<Counter>(1).await.unwrap();
+
+            assert_eq!(
+                gen_server::call::<Counter>(pid, Request::Get, TIMEOUT).await,
+                Ok(1)
+            );
+            assert_eq!(
+                gen_server::call::<Counter>(pid, Request::Add(2), TIMEOUT).await,
+                Ok(3)
+            );
+
+            gen_server::cast::<Counter>(pid, 4);
+            assert_eq!(
+                gen_server::call::<Counter>(pid, Request::Get, TIMEOUT).await,
+                Ok(7)
+            );
+
+            let monitor_ref = monitor(pid);
+
+            assert_eq!(
+                gen_server::call::<Counter>(pid, Request::Stop, TIMEOUT).await,
+                Ok(7)
+            );
+
+            let down = __receive().await.downcast::<Down>().unwrap();
+            assert_eq!(down.monitor_ref, monitor_ref);
+            assert_eq!(down.reason, ExitReason::Shutdown);
+
+            assert_eq!(
+                gen_server::call::<Counter>(pid, Request::Get, TIMEOUT).await,
+                Err(CallError::Exit(ExitReason::NoProc(pid)))
+            );
+        });
+
+        assert_eq!(handle.await, ExitReason::Normal);
+    }
+
+    #[async_metronome::test]
+    async fn call_crash() {
+        let (_, handle) = __spawn(async {
+            let pid = gen_server::start::<Counter>(1).await.unwrap();
+
+            let result = gen_server::call::<Counter>(pid, Request::Crash, TIMEOUT).await;
+            assert!(matches!(result, Err(CallError::Exit(ExitReason::Panic(_)))));
+        });
+
+        assert_eq!(handle.await, ExitReason::Normal);
+    }
+
+    #[async_metronome::test]
+    async fn call_timeout() {
+        let (_, handle) = __spawn(async {
+            let pid = gen_server::start::<Counter>(1).await.unwrap();
+
+            assert_eq!(
+                gen_server::call::<Counter>(pid, Request::Hang, TIMEOUT).await,
+                Err(CallError::Timeout)
+            );
+
+            // the server is still alive, the monitor is released
+            assert_eq!(
+                gen_server::call::<Counter>(pid, Request::Get, TIMEOUT).await,
+                Ok(1)
+            );
+            assert!(process_info(myself()).unwrap().monitors.is_empty());
+
+            gen_server::call::<Counter>(pid, Request::Stop, TIMEOUT)
+                .await
+                .unwrap();
+        });
+
+        assert_eq!(handle.await, ExitReason::Normal);
+    }
+
+    #[async_metronome::test]
+    async fn init_info() {
+        let (_, handle) = __spawn(async {
+            assert_eq!(
+                gen_server::start::<Counter>(0).await,
+                Err(ExitReason::custom("zero"))
+            );
+
+            let pid = gen_server::start::<Counter>(1).await.unwrap();
+            let monitor_ref = monitor(pid);
+
+            send(pid, "stop").unwrap();
+
+            let down = __receive().await.downcast::<Down>().unwrap();
+            assert_eq!(down.monitor_ref, monitor_ref);
+            assert_eq!(down.reason, ExitReason::shutdown("info"));
+        });
+
+        assert_eq!(handle.await, ExitReason::Normal);
+    }
+
+    #[async_metronome::test]
+    async fn call_mailbox() {
+        let (_, handle) = __spawn(async {
+            let parent = myself();
+            let pid = gen_server::start::<Counter>(1).await.unwrap();
+
+            send(myself(), "a").unwrap();
+            send(myself(), 2u64).unwrap();
+
+            assert_eq!(
+                gen_server::call::<Counter>(pid, Request::Get, TIMEOUT).await,
+                Ok(1)
+            );
+
+            // the older messages stay saved while the caller waits for the reply
+            assert_eq!(
+                gen_server::call::<Counter>(pid, Request::Saved(1), TIMEOUT).await,
+                Ok(2)
+            );
+
+            // a message arriving while the call waits is kept after the older ones
+            spawn(async move {
+                async_metronome::await_tick!(5);
+                send(parent, "b").unwrap();
+            });
+            assert_eq!(
+                gen_server::call::<Counter>(pid, Request::Hang, TIMEOUT).await,
+                Err(CallError::Timeout)
+            );
+
+            assert_eq!(process_info(myself()).unwrap().save_queue_len, 3);
+            assert!(__receive().await == "a");
+            assert!(__receive().await == 2u64);
+            assert!(__receive().await == "b");
+
+            gen_server::call::<Counter>(pid, Request::Stop, TIMEOUT)
+                .await
+                .unwrap();
+        });
+
+        assert_eq!(handle.await, ExitReason::Normal);
+    }
+
+    #[async_metronome::test]
+    async fn call_timeout_late_reply() {
+        let (_, handle) = __spawn(async {
+            let pid = gen_server::start::<Counter>(1).await.unwrap();
+
+            for tick in [20, 40, 60] {
+                assert_eq!(
+                    gen_server::call::<Counter>(
+                        pid,
+                        Request::Slow(tick),
+                        Duration::from_millis(10)
+                    )
+                    .await,
+                    Err(CallError::Timeout)
+                );
+            }
+
+            // the replies to the abandoned calls are dropped
+            assert_eq!(
+                gen_server::call::<Counter>(pid, Request::Get, TIMEOUT).await,
+                Ok(1)
+            );
+            let info = process_info(myself()).unwrap();
+            assert_eq!(info.message_queue_len, 0);
+            assert_eq!(info.save_queue_len, 0);
+
+            gen_server::call::<Counter>(pid, Request::Stop, TIMEOUT)
+                .await
+                .unwrap();
+        });
+
+        assert_eq!(handle.await, ExitReason::Normal);
+    }
+
+    // a call costs the same whatever the number of older messages, they are
+    // neither scanned nor moved
+    #[async_metronome::test]
+    async fn call_saved_untouched() {
+        let (_, handle) = __spawn(async {
+            let pid = gen_server::start::<Counter>(1).await.unwrap();
+
+            let calls = || async move {
+                let start = std::time::Instant::now();
+                for _ in 0..200 {
+                    gen_server::call::<Counter>(pid, Request::Get, TIMEOUT)
+                        .await
+                        .unwrap();
+                }
+                start.elapsed()
+            };
+
+            let empty = calls().await;
+
+            for n in 0..100_000u32 {
+                send(myself(), n).unwrap();
+            }
+            // the first mark moves the pending messages to the save queue
+            gen_server::call::<Counter>(pid, Request::Get, TIMEOUT)
+                .await
+                .unwrap();
+
+            let loaded = calls().await;
+            assert!(
+                loaded < empty * 4 + Duration::from_millis(20),
+                "{:?} {:?}",
+                empty,
+                loaded
+            );
+            assert_eq!(process_info(myself()).unwrap().save_queue_len, 100_000);
+
+            gen_server::call::<Counter>(pid, Request::Stop, TIMEOUT)
+                .await
+                .unwrap();
+        });
+
+        assert_eq!(handle.await, ExitReason::Normal);
+    }
+}
@@ -0,0 +1,75 @@
+#[cfg(test)]
+mod process_tests {
+    use async_metronome::await_tick;
+    use hastur::*;
+
+    #[async_metronome::test]
+    async fn process_info_queue() {
+        let (pid, handle) = __spawn(async {
+            trap_exit(true);
+            await_tick!(2);
+
+            receive! {
+                _: u64 => {},
+            };
+
+            let info = process_info(myself()).unwrap();
+            assert_eq!(info.message_queue_len, 0);
+            assert_eq!(info.save_queue_len, 2);
+            assert_eq!(info.memory, 2 * std::mem::size_of::<u32>());
+        });
+
+        await_tick!(1);
+        send(pid, 1u32).unwrap();
+        send(pid, 2u32).unwrap();
+        send(pid, 3u64).unwrap();
+        register("process_info_queue", pid).unwrap();
+
+        let info = process_info(pid).unwrap();
+        assert_eq!(info.message_queue_len, 3);
+        assert_eq!(info.save_queue_len, 0);
+        assert_eq!(
+            info.memory,
+            2 * std::mem::size_of::<u32>() + std::mem::size_of::<u64>()
+        );
+        assert!(info.trap_exit);
+        assert_eq!(info.registered_name.as_deref(), Some("process_info_queue"));
+
+        assert!(processes().contains(&pid));
+
+        assert_eq!(handle.await, ExitReason::Normal);
+
+        assert_eq!(process_info(pid), None);
+        assert!(!processes().contains(&pid));
+    }
+
+    #[async_metronome::test]
+    async fn process_info_links() {
+        let (_, handle) = __spawn(async {
+            let (child, handle) = __spawn_link(async {
+                __receive().await;
+            });
+
+            let monitor_ref = monitor(child);
+
+            let info = process_info(myself()).unwrap();
+            assert_eq!(info.links, vec![child]);
+            assert_eq!(info.monitors, vec![child]);
+
+            let info = process_info(child).unwrap();
+            assert_eq!(info.links, vec![myself()]);
+            assert_eq!(info.monitored_by, vec![myself()]);
+
+            send(child, ()).unwrap();
+            handle.await;
+
+            let info = process_info(myself()).unwrap();
+            assert!(info.links.is_empty());
+            assert!(info.monitors.is_empty());
+
+            demonitor(monitor_ref, true);
+        });
+
+        assert_eq!(handle.await, ExitReason::Normal);
+    }
+}
@@ -4,7 +4,6 @@ mod process_tests {
     use async_metronome::{self, assert_tick, await_tick};
     use futures::channel::oneshot;
     use futures::sink::SinkExt;
-    use simplelog;
     use std::time::Duration;
 
     use hastur::*;
@@ -17,7 +16,7 @@ mod process_tests {
                 panic!();
             });
 
-            assert_eq!(handle.await, ExitReason::Panic);
+            assert!(matches!(handle.await, ExitReason::Panic(_)));
         });
 
         assert_eq!(handle.await, ExitReason::Normal);
@@ -54,6 +53,230 @@ mod process_tests {
             unreachable!();
         });
 
-        assert_eq!(handle.await, ExitReason::Panic);
+        assert!(matches!(handle.await, ExitReason::Panic(_)));
+    }
+
+    #[async_metronome::test]
+    async fn spawn_monitor_down() {
+        let (_, handle) = __spawn(async {
+            let (pid, monitor_ref, _) = __spawn_opt(
+                async {
+                    await_tick!(1);
+                    panic!();
+                },
+                SpawnOptBuilder::default().monitor(true).build().unwrap(),
+            );
+
+            let down = __receive().await.downcast::<Down>().unwrap();
+
+            assert_eq!(down.monitor_ref, monitor_ref.unwrap());
+            assert_eq!(down.pid, pid);
+            assert!(matches!(down.reason, ExitReason::Panic(_)));
+        });
+
+        assert_eq!(handle.await, ExitReason::Normal);
+    }
+
+    #[async_metronome::test]
+    async fn monitor_noproc() {
+        let (_, handle) = __spawn(async {
+            let (pid, handle) = __spawn(async {});
+            handle.await;
+
+            let monitor_ref = monitor(pid);
+
+            let down = __receive().await.downcast::<Down>().unwrap();
+
+            assert_eq!(
+                down,
+                Down {
+                    monitor_ref,
+                    pid,
+                    reason: ExitReason::NoProc(pid),
+                }
+            );
+        });
+
+        assert_eq!(handle.await, ExitReason::Normal);
+    }
+
+    #[async_metronome::test]
+    async fn demonitor_flush() {
+        let (_, handle) = __spawn(async {
+            let (pid, handle) = __spawn(async {
+                __receive().await;
+            });
+
+            let monitor_ref = monitor(pid);
+            send(pid, ());
+            handle.await;
+
+            assert!(!demonitor(monitor_ref, true));
+
+            send(myself(), 1u32);
+            assert_eq!(__receive().await, 1u32);
+        });
+
+        assert_eq!(handle.await, ExitReason::Normal);
+    }
+
+    #[async_metronome::test]
+    async fn monitoring_exit() {
+        let (_, handle) = __spawn(async {
+            let (pid, handle) = __spawn(async {
+                __receive().await;
+            });
+
+            for _ in 0..3 {
+                let (_, monitoring) = __spawn(async move {
+                    monitor(pid);
+                });
+                assert_eq!(monitoring.await, ExitReason::Normal);
+            }
+
+            // the monitors of an exited process are released
+            assert!(process_info(pid).unwrap().monitored_by.is_empty());
+
+            send(pid, ());
+            handle.await;
+        });
+
+        assert_eq!(handle.await, ExitReason::Normal);
+    }
+
+    #[async_metronome::test]
+    async fn spawn_link_unlink() {
+        let (_, handle) = __spawn(async {
+            let (pid, handle) = __spawn_link(async {
+                await_tick!(1);
+                panic!();
+            });
+
+            unlink(pid);
+
+            assert!(matches!(handle.await, ExitReason::Panic(_)));
+        });
+
+        assert_eq!(handle.await, ExitReason::Normal);
+    }
+
+    #[async_metronome::test]
+    async fn kill_trapped() {
+        let (_, handle) = __spawn(async {
+            let myself = myself();
+
+            let (pid, handle) = __spawn(async move {
+                trap_exit(true);
+
+                let exit = __receive().await.downcast::<Exit>().unwrap();
+                assert_eq!(exit, Exit(myself, ExitReason::custom("reason")));
+            });
+
+            await_tick!(1);
+            kill(pid, ExitReason::custom("reason"));
+
+            assert_eq!(handle.await, ExitReason::Normal);
+        });
+
+        assert_eq!(handle.await, ExitReason::Normal);
+    }
+
+    #[async_metronome::test]
+    async fn kill_untrappable() {
+        let (_, handle) = __spawn(async {
+            let (pid, handle) = __spawn(async {
+                trap_exit(true);
+
+                let (_, handle) = __spawn_link(async {
+                    __receive().await;
+                });
+
+                assert_eq!(handle.await, ExitReason::Killed);
+            });
+
+            await_tick!(1);
+            kill(pid, ExitReason::Kill);
+
+            assert_eq!(handle.await, ExitReason::Killed);
+        });
+
+        assert_eq!(handle.await, ExitReason::Normal);
+    }
+
+    #[async_metronome::test]
+    async fn trap_exit_linked() {
+        let (_, handle) = __spawn(async {
+            trap_exit(true);
+
+            let (pid, _) = __spawn_link(async {
+                panic!();
+            });
+
+            let exit = __receive().await.downcast::<Exit>().unwrap();
+            assert!(matches!(exit, Exit(from, ExitReason::Panic(_)) if from == pid));
+        });
+
+        assert_eq!(handle.await, ExitReason::Normal);
+    }
+
+    #[async_metronome::test]
+    async fn trap_exit_order() {
+        let (_, handle) = __spawn(async {
+            let myself = myself();
+
+            let (pid, handle) = __spawn(async move {
+                trap_exit(true);
+                await_tick!(2);
+
+                assert_eq!(__receive().await, 1u32);
+                assert_eq!(__receive().await, Exit(myself, ExitReason::Shutdown));
+                assert_eq!(__receive().await, 2u32);
+            });
+
+            await_tick!(1);
+            send(pid, 1u32);
+            kill(pid, ExitReason::Shutdown);
+            send(pid, 2u32);
+
+            assert_eq!(handle.await, ExitReason::Normal);
+        });
+
+        assert_eq!(handle.await, ExitReason::Normal);
+    }
+
+    #[async_metronome::test]
+    async fn trap_exit_link_noproc() {
+        let (_, handle) = __spawn(async {
+            trap_exit(true);
+
+            let (pid, handle) = __spawn(async {});
+            handle.await;
+
+            link(pid);
+
+            let exit = __receive().await.downcast::<Exit>().unwrap();
+            assert_eq!(exit, Exit(pid, ExitReason::NoProc(pid)));
+        });
+
+        assert_eq!(handle.await, ExitReason::Normal);
+    }
+
+    #[async_metronome::test]
+    async fn exit_payload_linked() {
+        let (_, handle) = __spawn(async {
+            trap_exit(true);
+
+            let (pid, handle) = __spawn_link(async {
+                exit(ExitReason::shutdown(42u32)).await;
+            });
+
+            assert_eq!(handle.await, ExitReason::shutdown(42u32));
+
+            let exit = __receive().await.downcast::<Exit>().unwrap();
+            assert_eq!(exit, Exit(pid, ExitReason::shutdown(42u32)));
+            assert_ne!(exit, Exit(pid, ExitReason::shutdown(43u32)));
+        });
+
+        assert_eq!(handle.await, ExitReason::Normal);
     }
 }
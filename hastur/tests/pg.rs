@@ -0,0 +1,48 @@
+#[cfg(test)]
+mod process_tests {
+    use async_metronome::await_tick;
+    use hastur::*;
+
+    #[async_metronome::test]
+    async fn join_broadcast() {
+        let (pid1, handle1) = __spawn(async {
+            assert_eq!(__receive().await, 1u32);
+        });
+        let (pid2, handle2) = __spawn(async {
+            assert_eq!(__receive().await, 1u32);
+        });
+
+        assert!(join("join_broadcast", pid1));
+        assert!(join("join_broadcast", pid2));
+        assert_eq!(members("join_broadcast"), vec![pid1, pid2]);
+
+        broadcast("join_broadcast", 1u32);
+
+        assert_eq!(handle1.await, ExitReason::Normal);
+        assert_eq!(handle2.await, ExitReason::Normal);
+
+        // released on exit
+        assert!(members("join_broadcast").is_empty());
+    }
+
+    #[async_metronome::test]
+    async fn join_leave() {
+        let (pid, handle) = __spawn(async {
+            await_tick!(1);
+        });
+
+        assert!(join("join_leave", pid));
+        assert!(join("join_leave", pid));
+
+        assert!(leave("join_leave", pid));
+        assert_eq!(members("join_leave"), vec![pid]);
+
+        assert!(leave("join_leave", pid));
+        assert!(!leave("join_leave", pid));
+        assert!(members("join_leave").is_empty());
+
+        handle.await;
+
+        assert!(!join("join_leave", pid));
+    }
+}
@@ -0,0 +1,223 @@
+#[cfg(test)]
+mod process_tests {
+    use std::time::Duration;
+
+    use async_metronome::{assert_tick, await_tick};
+
+    use hastur::*;
+
+    #[async_metronome::test]
+    async fn after_timeout() {
+        let (_, handle) = __spawn(async {
+            send(myself(), "skipped").unwrap();
+
+            let timeout = receive! {
+                _: u32 => { false },
+                after Duration::from_millis(10) => { true },
+            };
+            assert!(timeout);
+            assert_tick!(10);
+
+            // the skipped message is still in the mailbox
+            assert!(__receive().await == "skipped");
+        });
+
+        assert_eq!(handle.await, ExitReason::Normal);
+    }
+
+    #[async_metronome::test]
+    async fn after_message() {
+        let (_, handle) = __spawn(async {
+            let pid = myself();
+
+            spawn(async move {
+                await_tick!(5);
+                send(pid, 1u32).unwrap();
+            });
+
+            let n = receive! {
+                n: u32 => { n },
+                after Duration::from_millis(10) => { 0 },
+            };
+            assert_eq!(n, 1);
+            assert_tick!(5);
+        });
+
+        assert_eq!(handle.await, ExitReason::Normal);
+    }
+
+    #[async_metronome::test]
+    async fn after_zero() {
+        let (_, handle) = __spawn(async {
+            send(myself(), "skipped").unwrap();
+            send(myself(), 1u32).unwrap();
+
+            let n = receive! {
+                n: u32 => { n },
+                after 0 => { 0 },
+            };
+            assert_eq!(n, 1);
+
+            let n = receive! {
+                n: u32 => { n },
+                after 0 => { 0 },
+            };
+            assert_eq!(n, 0);
+            assert_tick!(0);
+        });
+
+        assert_eq!(handle.await, ExitReason::Normal);
+    }
+
+    #[tokio::test]
+    async fn after_tokio() {
+        let reason = run(async {
+            let timeout = receive! {
+                _: u32 => { false },
+                after 10 => { true },
+            };
+            assert!(timeout);
+        })
+        .await;
+
+        assert_eq!(reason, ExitReason::Normal);
+    }
+
+    #[async_metronome::test]
+    async fn guard() {
+        let (_, handle) = __spawn(async {
+            send(myself(), 5u32).unwrap();
+            send(myself(), "short".to_string()).unwrap();
+            send(myself(), 20u32).unwrap();
+            send(myself(), "long enough".to_string()).unwrap();
+
+            let n = receive! {
+                n: u32 if n > 10 => { n },
+            };
+            assert_eq!(n, 20);
+
+            let s = receive! {
+                s: String if s.len() > 5 => { s },
+            };
+            assert_eq!(s, "long enough");
+
+            // failed guards fall through to the next patterns
+            let n = receive! {
+                n: u32 if n > 10 => { n },
+                n: u32 => { n + 100 },
+            };
+            assert_eq!(n, 105);
+
+            let any = receive! {
+                e: _ if e.is::<u32>() => { false },
+                e: _ if e.downcast_ref::<String>().is_some_and(|s| s == "short") => { true },
+            };
+            assert!(any);
+            assert_eq!(process_info(myself()).unwrap().save_queue_len, 0);
+            assert_eq!(process_info(myself()).unwrap().message_queue_len, 0);
+        });
+
+        assert_eq!(handle.await, ExitReason::Normal);
+    }
+
+    #[derive(Debug, PartialEq)]
+    enum Cmd {
+        Put { key: String, value: u32 },
+        Delete(String),
+    }
+
+    #[derive(Debug, PartialEq)]
+    enum Reply {
+        Ok(u32),
+        Error,
+    }
+
+    #[async_metronome::test]
+    async fn destructure() {
+        let (_, handle) = __spawn(async {
+            send(myself(), 1u32).unwrap();
+            send(myself(), Cmd::Delete("a".to_string())).unwrap();
+            send(myself(), (2u64, Reply::Ok(2))).unwrap();
+            send(myself(), (1u64, Reply::Error)).unwrap();
+            send(myself(), (1u64, Reply::Ok(1))).unwrap();
+            send(
+                myself(),
+                Cmd::Put {
+                    key: "b".to_string(),
+                    value: 3,
+                },
+            )
+            .unwrap();
+
+            let n = receive! {
+                mut n: u32 => { n += 1; n },
+            };
+            assert_eq!(n, 2);
+
+            let (key, value) = receive! {
+                Cmd::Put { key, value }: Cmd => { (key, value) },
+            };
+            assert_eq!((key.as_str(), value), ("b", 3));
+
+            send(
+                myself(),
+                Cmd::Put {
+                    key: "c".to_string(),
+                    value: 4,
+                },
+            )
+            .unwrap();
+            let (key, value) = receive! {
+                Cmd::Put { mut key, value }: Cmd => { key.push('x'); (key, value) },
+            };
+            assert_eq!((key.as_str(), value), ("cx", 4));
+
+            let v = receive! {
+                (1, Reply::Ok(v)): (u64, Reply) => { v },
+            };
+            assert_eq!(v, 1);
+
+            let v = receive! {
+                (tag, Reply::Ok(v)): (u64, Reply) if tag > 1 => { v },
+            };
+            assert_eq!(v, 2);
+
+            // unmatched messages stay in order
+            assert!(__receive().await == Cmd::Delete("a".to_string()));
+            assert!(__receive().await == (1u64, Reply::Error));
+        });
+
+        assert_eq!(handle.await, ExitReason::Normal);
+    }
+
+    #[async_metronome::test]
+    async fn alternation() {
+        let (_, handle) = __spawn(async {
+            send(myself(), "skipped").unwrap();
+            send(myself(), 1u64).unwrap();
+            send(myself(), 2u32).unwrap();
+            send(myself(), 3u64).unwrap();
+
+            let n = receive! {
+                n: u32 | u64 => { n },
+            };
+            assert!(n == 1u64);
+
+            let n = receive! {
+                n: u32 | u64 if n.is::<u64>() => { n },
+            };
+            assert!(n == 3u64);
+
+            let n = receive! {
+                _: u8 | String => { 0 },
+                n: u16 | u32 => { n.downcast::<u32>().unwrap() },
+            };
+            assert_eq!(n, 2);
+
+            // unmatched messages stay in the mailbox
+            assert!(__receive().await == "skipped");
+        });
+
+        assert_eq!(handle.await, ExitReason::Normal);
+    }
+}
@@ -0,0 +1,60 @@
+#[cfg(test)]
+mod process_tests {
+    use async_metronome::await_tick;
+    use hastur::*;
+
+    #[async_metronome::test]
+    async fn register_whereis() {
+        let (pid, handle) = __spawn(async {
+            let message = __receive().await;
+            assert_eq!(message, 1u32);
+        });
+
+        assert_eq!(register("register_whereis", pid), Ok(()));
+        assert_eq!(whereis("register_whereis"), Some(pid));
+
+        assert_eq!(send_named("register_whereis", 1u32), Ok(()));
+        assert_eq!(handle.await, ExitReason::Normal);
+
+        // released on exit
+        assert_eq!(whereis("register_whereis"), None);
+        assert_eq!(
+            send_named("register_whereis", 1u32),
+            Err(RegistryError::Unregistered)
+        );
+    }
+
+    #[async_metronome::test]
+    async fn register_taken() {
+        let (pid1, handle1) = __spawn(async {
+            await_tick!(1);
+        });
+        let (pid2, handle2) = __spawn(async {
+            await_tick!(1);
+        });
+
+        assert_eq!(register("register_taken", pid1), Ok(()));
+        assert_eq!(
+            register("register_taken", pid2),
+            Err(RegistryError::Taken(pid1))
+        );
+        assert_eq!(
+            register("register_taken_other", pid1),
+            Err(RegistryError::Registered("register_taken".to_string()))
+        );
+
+        assert_eq!(unregister("register_taken"), Ok(()));
+        assert_eq!(
+            unregister("register_taken"),
+            Err(RegistryError::Unregistered)
+        );
+        assert_eq!(register("register_taken", pid2), Ok(()));
+        assert_eq!(register("register_taken_other", pid1), Ok(()));
+
+        handle1.await;
+        handle2.await;
+
+        assert_eq!(whereis("register_taken"), None);
+        assert_eq!(whereis("register_taken_other"), None);
+    }
+}
@@ -59,4 +59,15 @@ mod process_tests {
 
         assert_eq!(handle.await, ExitReason::Normal);
     }
+
+    #[async_metronome::test]
+    async fn send_noproc() {
+        let (pid, _, handle) = __spawn_opt(async {}, SpawnOpt::default());
+
+        assert_eq!(handle.await, ExitReason::Normal);
+
+        let error = send(pid, 5u32).unwrap_err();
+        assert_eq!(error.0, pid);
+        assert_eq!(error.into_inner(), 5u32);
+    }
 }
@@ -41,6 +41,23 @@ mod process_tests {
         let _ = myself();
     }
 
+    #[async_metronome::test]
+    async fn try_myself_noproc() {
+        assert_eq!(try_myself(), Err(HasturError::NoProcess));
+
+        let (_, _, handle) = __spawn_opt(
+            async {
+                assert_eq!(try_myself(), Ok(myself()));
+            },
+            SpawnOpt::default(),
+        );
+
+        assert_eq!(handle.await, ExitReason::Normal);
+
+        // the pid is not left behind on the thread
+        assert_eq!(try_myself(), Err(HasturError::NoProcess));
+    }
+
     // normal termination -> ExitReason::Normal
     #[async_metronome::test]
     async fn exit_normal() {
@@ -53,17 +70,23 @@ mod process_tests {
         assert_eq!(handle.await, ExitReason::Normal);
     }
 
-    // panic termination -> ExitReason::Panic
+    // panic termination -> ExitReason::Panic with message and location
     #[async_metronome::test]
     async fn exit_panic() {
         let (_, _, handle) = __spawn_opt(
             async {
-                panic!();
+                panic!("boom {}", 42);
             },
             SpawnOpt::default(),
         );
 
-        assert_eq!(handle.await, ExitReason::Panic);
+        match handle.await {
+            ExitReason::Panic(info) => {
+                assert_eq!(info.message, "boom 42");
+                assert!(info.location.unwrap().contains("tests/spawn.rs:"));
+            }
+            reason => panic!("unexpected {reason:?}"),
+        }
     }
 
     #[async_metronome::test]
@@ -78,4 +101,60 @@ mod process_tests {
 
         assert_eq!(handle.await, ExitReason::Normal);
     }
+
+    #[async_metronome::test]
+    async fn self_exit_custom() {
+        let (_, _, handle) = __spawn_opt(
+            async move {
+                exit(ExitReason::custom("failure")).await;
+                unreachable!();
+            },
+            SpawnOpt::default(),
+        );
+
+        match handle.await {
+            ExitReason::Custom(payload) => {
+                assert_eq!(payload.downcast_ref::<&str>(), Some(&"failure"));
+            }
+            reason => panic!("unexpected {reason:?}"),
+        }
+    }
+
+    #[async_metronome::test]
+    async fn is_alive_exit() {
+        let (pid, _, handle) = __spawn_opt(
+            async {
+                assert!(is_alive(myself()));
+            },
+            SpawnOpt::default(),
+        );
+
+        assert!(is_alive(pid));
+        assert_eq!(handle.await, ExitReason::Normal);
+        assert!(!is_alive(pid));
+    }
+
+    // run makes the future a root process
+    #[async_metronome::test]
+    async fn run_root() {
+        let reason = run(async {
+            let (_, handle) = __spawn_link(async {});
+            assert_eq!(handle.await, ExitReason::Normal);
+
+            exit(ExitReason::shutdown("done")).await;
+        })
+        .await;
+
+        assert_eq!(reason, ExitReason::shutdown("done"));
+    }
+
+    #[hastur::main]
+    async fn root() {
+        spawn_link(async {});
+    }
+
+    #[test]
+    fn main_root() {
+        assert_eq!(root(), ExitReason::Normal);
+    }
 }
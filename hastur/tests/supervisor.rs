@@ -0,0 +1,274 @@
+#[cfg(test)]
+mod process_tests {
+    use std::time::Duration;
+
+    use hastur::supervisor::{self, Child};
+    use hastur::*;
+
+    fn worker(id: &str) -> ChildSpec {
+        ChildSpec::new(id, || async {
+            Ok(spawn_link(async { while __receive().await != "stop" {} }))
+        })
+    }
+
+    async fn children(sup: Pid) -> Vec<Option<Pid>> {
+        supervisor::which_children(sup)
+            .await
+            .unwrap()
+            .into_iter()
+            .map(|child| child.pid)
+            .collect()
+    }
+
+    // the exit reaches the supervisor before the down reaches us
+    async fn exit_child(pid: Pid, reason: ExitReason) {
+        let monitor_ref = monitor(pid);
+        kill(pid, reason);
+
+        let down = __receive().await.downcast::<Down>().unwrap();
+        assert_eq!(down.monitor_ref, monitor_ref);
+    }
+
+    async fn stop(sup: Pid) -> ExitReason {
+        let monitor_ref = monitor(sup);
+        kill(sup, ExitReason::Shutdown);
+
+        let down = __receive().await.downcast::<Down>().unwrap();
+        assert_eq!(down.monitor_ref, monitor_ref);
+        down.reason
+    }
+
+    async fn start(strategy: Strategy, children: Vec<ChildSpec>) -> Pid {
+        let opt = SupervisorOptBuilder::default()
+            .strategy(strategy)
+            .build()
+            .unwrap();
+
+        supervisor::start(opt, children).await.unwrap()
+    }
+
+    #[async_metronome::test]
+    async fn one_for_one() {
+        let (_, handle) = __spawn(async {
+            let sup = start(Strategy::OneForOne, vec![worker("a"), worker("b")]).await;
+            let before = children(sup).await;
+
+            exit_child(before[0].unwrap(), ExitReason::custom("crash")).await;
+
+            let after = children(sup).await;
+            assert!(after[0].is_some_and(|pid| Some(pid) != before[0]));
+            assert_eq!(after[1], before[1]);
+
+            assert_eq!(stop(sup).await, ExitReason::Shutdown);
+            assert!(!is_alive(after[0].unwrap()));
+            assert!(!is_alive(after[1].unwrap()));
+        });
+
+        assert_eq!(handle.await, ExitReason::Normal);
+    }
+
+    #[async_metronome::test]
+    async fn one_for_all() {
+        let (_, handle) = __spawn(async {
+            let sup = start(Strategy::OneForAll, vec![worker("a"), worker("b")]).await;
+            let before = children(sup).await;
+
+            exit_child(before[1].unwrap(), ExitReason::custom("crash")).await;
+
+            let after = children(sup).await;
+            assert!(after[0].is_some_and(|pid| Some(pid) != before[0]));
+            assert!(after[1].is_some_and(|pid| Some(pid) != before[1]));
+            assert!(!is_alive(before[0].unwrap()));
+
+            assert_eq!(stop(sup).await, ExitReason::Shutdown);
+        });
+
+        assert_eq!(handle.await, ExitReason::Normal);
+    }
+
+    #[async_metronome::test]
+    async fn rest_for_one() {
+        let (_, handle) = __spawn(async {
+            let sup = start(
+                Strategy::RestForOne,
+                vec![worker("a"), worker("b"), worker("c")],
+            )
+            .await;
+            let before = children(sup).await;
+
+            exit_child(before[1].unwrap(), ExitReason::custom("crash")).await;
+
+            let after = children(sup).await;
+            assert_eq!(after[0], before[0]);
+            assert!(after[1].is_some_and(|pid| Some(pid) != before[1]));
+            assert!(after[2].is_some_and(|pid| Some(pid) != before[2]));
+            assert!(!is_alive(before[2].unwrap()));
+
+            assert_eq!(stop(sup).await, ExitReason::Shutdown);
+        });
+
+        assert_eq!(handle.await, ExitReason::Normal);
+    }
+
+    #[async_metronome::test]
+    async fn restart_types() {
+        let (_, handle) = __spawn(async {
+            let sup = start(
+                Strategy::OneForOne,
+                vec![
+                    worker("permanent"),
+                    worker("transient").restart(Restart::Transient),
+                    worker("temporary").restart(Restart::Temporary),
+                ],
+            )
+            .await;
+            let before = children(sup).await;
+
+            exit_child(before[1].unwrap(), ExitReason::Shutdown).await;
+            exit_child(before[2].unwrap(), ExitReason::custom("crash")).await;
+
+            assert_eq!(
+                supervisor::which_children(sup).await.unwrap(),
+                vec![
+                    Child {
+                        id: "permanent".to_string(),
+                        pid: before[0],
+                    },
+                    Child {
+                        id: "transient".to_string(),
+                        pid: None,
+                    },
+                ]
+            );
+
+            assert_eq!(stop(sup).await, ExitReason::Shutdown);
+        });
+
+        assert_eq!(handle.await, ExitReason::Normal);
+    }
+
+    #[async_metronome::test]
+    async fn intensity() {
+        let (_, handle) = __spawn(async {
+            let opt = SupervisorOptBuilder::default()
+                .intensity(1)
+                .build()
+                .unwrap();
+            let sup = supervisor::start(opt, vec![worker("a"), worker("b")])
+                .await
+                .unwrap();
+            let monitor_ref = monitor(sup);
+
+            exit_child(children(sup).await[0].unwrap(), ExitReason::custom("crash")).await;
+
+            let pids = children(sup).await;
+            kill(pids[0].unwrap(), ExitReason::custom("crash"));
+
+            let down = __receive().await.downcast::<Down>().unwrap();
+            assert_eq!(down.monitor_ref, monitor_ref);
+            assert_eq!(down.reason, ExitReason::Shutdown);
+            assert!(!is_alive(pids[1].unwrap()));
+        });
+
+        assert_eq!(handle.await, ExitReason::Normal);
+    }
+
+    #[async_metronome::test]
+    async fn intensity_period() {
+        let (_, handle) = __spawn(async {
+            let opt = SupervisorOptBuilder::default()
+                .intensity(1)
+                .period(Duration::from_millis(10))
+                .build()
+                .unwrap();
+            let sup = supervisor::start(opt, vec![worker("a")]).await.unwrap();
+            let monitor_ref = monitor(sup);
+
+            exit_child(children(sup).await[0].unwrap(), ExitReason::custom("crash")).await;
+
+            // the first restart is out of the period
+            async_metronome::await_tick!(20);
+            exit_child(children(sup).await[0].unwrap(), ExitReason::custom("crash")).await;
+            assert!(children(sup).await[0].is_some());
+
+            kill(children(sup).await[0].unwrap(), ExitReason::custom("crash"));
+
+            let down = __receive().await.downcast::<Down>().unwrap();
+            assert_eq!(down.monitor_ref, monitor_ref);
+            assert_eq!(down.reason, ExitReason::Shutdown);
+        });
+
+        assert_eq!(handle.await, ExitReason::Normal);
+    }
+
+    #[async_metronome::test]
+    async fn temporary_sibling() {
+        let (_, handle) = __spawn(async {
+            let sup = start(
+                Strategy::OneForAll,
+                vec![worker("a"), worker("temporary").restart(Restart::Temporary)],
+            )
+            .await;
+            let before = children(sup).await;
+
+            exit_child(before[0].unwrap(), ExitReason::custom("crash")).await;
+
+            let after = supervisor::which_children(sup).await.unwrap();
+            assert_eq!(after.len(), 1);
+            assert_eq!(after[0].id, "a");
+            assert!(after[0].pid.is_some_and(|pid| Some(pid) != before[0]));
+            assert!(!is_alive(before[1].unwrap()));
+
+            assert_eq!(stop(sup).await, ExitReason::Shutdown);
+        });
+
+        assert_eq!(handle.await, ExitReason::Normal);
+    }
+
+    #[async_metronome::test]
+    async fn shutdown_timeout() {
+        let (_, handle) = __spawn(async {
+            let stubborn = ChildSpec::new("stubborn", || async {
+                Ok(spawn_link(async {
+                    trap_exit(true);
+                    loop {
+                        __receive().await;
+                    }
+                }))
+            })
+            .shutdown(Shutdown::Timeout(Duration::from_millis(10)));
+
+            let sup = start(Strategy::OneForOne, vec![stubborn, worker("a")]).await;
+            let pids = children(sup).await;
+            let child_ref = monitor(pids[0].unwrap());
+            let sup_ref = monitor(sup);
+
+            kill(sup, ExitReason::Shutdown);
+
+            // children are gone before the supervisor exits
+            let down = __receive().await.downcast::<Down>().unwrap();
+            assert_eq!(down.monitor_ref, child_ref);
+            assert_eq!(down.reason, ExitReason::Killed);
+
+            let down = __receive().await.downcast::<Down>().unwrap();
+            assert_eq!(down.monitor_ref, sup_ref);
+            assert_eq!(down.reason, ExitReason::Shutdown);
+        });
+
+        assert_eq!(handle.await, ExitReason::Normal);
+    }
+
+    #[async_metronome::test]
+    async fn init_failure() {
+        let (_, handle) = __spawn(async {
+            let failing = ChildSpec::new("failing", || async { Err(ExitReason::custom("init")) });
+
+            assert_eq!(
+                supervisor::start(SupervisorOpt::default(), vec![worker("a"), failing]).await,
+                Err(ExitReason::custom("init"))
+            );
+        });
+
+        assert_eq!(handle.await, ExitReason::Normal);
+    }
+}
@@ -0,0 +1,116 @@
+#[cfg(test)]
+mod process_tests {
+    use std::time::Duration;
+
+    use async_metronome::await_tick;
+
+    use hastur::*;
+
+    const TIMEOUT: Duration = Duration::from_millis(10);
+
+    #[async_metronome::test]
+    async fn await_value() {
+        let (_, handle) = __spawn(async {
+            let task = Task::spawn(async { 1 + 2 });
+            assert_eq!(process_info(myself()).unwrap().links, vec![task.pid()]);
+
+            assert_eq!(task.await_timeout(TIMEOUT).await, Ok(3));
+            assert!(process_info(myself()).unwrap().monitors.is_empty());
+        });
+
+        assert_eq!(handle.await, ExitReason::Normal);
+    }
+
+    #[async_metronome::test]
+    async fn await_exit() {
+        let (_, handle) = __spawn(async {
+            trap_exit(true);
+
+            let task = Task::spawn(async { panic!("task") });
+            let pid = task.pid();
+
+            let result = task.await_timeout(TIMEOUT).await;
+            assert!(matches!(result, Err(TaskError::Exit(ExitReason::Panic(_)))));
+
+            let Exit(from, reason) = __receive().await.downcast::<Exit>().unwrap();
+            assert_eq!(from, pid);
+            assert!(matches!(reason, ExitReason::Panic(_)));
+        });
+
+        assert_eq!(handle.await, ExitReason::Normal);
+    }
+
+    #[async_metronome::test]
+    async fn await_timeout() {
+        let (_, handle) = __spawn(async {
+            let task = Task::spawn(async {
+                __receive().await;
+            });
+            let pid = task.pid();
+
+            assert_eq!(task.await_timeout(TIMEOUT).await, Err(TaskError::Timeout));
+            assert!(!is_alive(pid));
+        });
+
+        assert_eq!(handle.await, ExitReason::Normal);
+    }
+
+    #[async_metronome::test]
+    async fn yield_many() {
+        let (_, handle) = __spawn(async {
+            send(myself(), "other").unwrap();
+
+            let tasks = vec![
+                Task::spawn(async { 1 }),
+                Task::spawn(async {
+                    __receive().await;
+                    2
+                }),
+                Task::spawn(async {
+                    await_tick!(5);
+                    3
+                }),
+            ];
+
+            let mut results = Task::yield_many(tasks, TIMEOUT).await;
+            assert_eq!(results[0].1, Some(Ok(1)));
+            assert_eq!(results[1].1, None);
+            assert_eq!(results[2].1, Some(Ok(3)));
+
+            // unrelated messages stay in the mailbox
+            assert!(__receive().await == "other");
+
+            let (task, _) = results.remove(1);
+            assert_eq!(task.shutdown(Shutdown::BrutalKill).await, None);
+        });
+
+        assert_eq!(handle.await, ExitReason::Normal);
+    }
+
+    #[async_metronome::test]
+    async fn shutdown() {
+        let (_, handle) = __spawn(async {
+            let task = Task::spawn(async { 1 });
+            await_tick!(1);
+            assert_eq!(task.shutdown(Shutdown::Timeout(TIMEOUT)).await, Some(Ok(1)));
+
+            let task = Task::spawn(async {
+                trap_exit(true);
+                loop {
+                    __receive().await;
+                }
+            });
+            let pid = task.pid();
+            await_tick!(1);
+
+            assert_eq!(
+                task.shutdown(Shutdown::Timeout(TIMEOUT)).await,
+                None::<Result<(), _>>
+            );
+            assert!(!is_alive(pid));
+            assert!(process_info(myself()).unwrap().links.is_empty());
+        });
+
+        assert_eq!(handle.await, ExitReason::Normal);
+    }
+}
@@ -0,0 +1,155 @@
+#[cfg(test)]
+mod process_tests {
+    use std::time::Duration;
+
+    use async_metronome::{assert_tick, await_tick};
+
+    use hastur::*;
+
+    #[async_metronome::test]
+    async fn send_after_order() {
+        let (_, handle) = __spawn(async {
+            send_after(Duration::from_millis(20), myself(), 2u32);
+            send_after(Duration::from_millis(10), myself(), 1u32);
+            send_after(Duration::from_millis(20), myself(), 3u32);
+
+            assert!(__receive().await == 1u32);
+            assert_tick!(10);
+            assert!(__receive().await == 2u32);
+            assert!(__receive().await == 3u32);
+            assert_tick!(20);
+        });
+
+        assert_eq!(handle.await, ExitReason::Normal);
+    }
+
+    #[async_metronome::test]
+    async fn cancel_read() {
+        let (_, handle) = __spawn(async {
+            let timer_ref = send_after(Duration::from_millis(10), myself(), ());
+            assert_eq!(read_timer(timer_ref), Some(Duration::from_millis(10)));
+
+            await_tick!(4);
+            assert_eq!(read_timer(timer_ref), Some(Duration::from_millis(6)));
+            assert_eq!(cancel_timer(timer_ref), Some(Duration::from_millis(6)));
+            assert_eq!(cancel_timer(timer_ref), None);
+            assert_eq!(read_timer(timer_ref), None);
+
+            await_tick!(20);
+            assert_eq!(process_info(myself()).unwrap().message_queue_len, 0);
+        });
+
+        assert_eq!(handle.await, ExitReason::Normal);
+    }
+
+    #[async_metronome::test]
+    async fn owner_exit() {
+        let (_, handle) = __spawn(async {
+            let parent = myself();
+
+            let (_, child) =
+                __spawn(async move { send_after(Duration::from_millis(10), parent, ()) });
+            assert_eq!(child.await, ExitReason::Normal);
+
+            await_tick!(20);
+            assert_eq!(process_info(myself()).unwrap().message_queue_len, 0);
+        });
+
+        assert_eq!(handle.await, ExitReason::Normal);
+    }
+
+    #[tokio::test]
+    async fn send_after_tokio() {
+        let reason = run(async {
+            let timer_ref = send_after(Duration::from_millis(10), myself(), "tick");
+            assert!(read_timer(timer_ref).is_some());
+
+            assert!(__receive().await == "tick");
+            assert_eq!(read_timer(timer_ref), None);
+        })
+        .await;
+
+        assert_eq!(reason, ExitReason::Normal);
+    }
+
+    #[async_metronome::test]
+    async fn send_interval_cancel() {
+        let (_, handle) = __spawn(async {
+            let mut n = 0u32;
+            let timer_ref = send_interval(Duration::from_millis(10), myself(), move || {
+                n += 1;
+                n
+            });
+
+            for expected in 1..=3u32 {
+                assert!(__receive().await == expected);
+                assert_tick!(expected as usize * 10);
+            }
+            assert_eq!(read_timer(timer_ref), Some(Duration::from_millis(10)));
+
+            assert_eq!(cancel_timer(timer_ref), Some(Duration::from_millis(10)));
+
+            await_tick!(50);
+            assert_eq!(process_info(myself()).unwrap().message_queue_len, 0);
+        });
+
+        assert_eq!(handle.await, ExitReason::Normal);
+    }
+
+    #[async_metronome::test]
+    async fn send_interval_exit() {
+        let (_, handle) = __spawn(async {
+            let parent = myself();
+
+            // the owner exits
+            let (_, child) = __spawn(async move {
+                send_interval(Duration::from_millis(10), parent, || ());
+            });
+            assert_eq!(child.await, ExitReason::Normal);
+
+            // the target exits
+            let (target, handle) = __spawn(async {
+                __receive().await;
+            });
+            let timer_ref = send_interval(Duration::from_millis(10), target, || ());
+            assert_eq!(handle.await, ExitReason::Normal);
+            assert!(read_timer(timer_ref).is_some());
+
+            await_tick!(30);
+            assert_eq!(read_timer(timer_ref), None);
+            assert_eq!(process_info(myself()).unwrap().message_queue_len, 0);
+        });
+
+        assert_eq!(handle.await, ExitReason::Normal);
+    }
+
+    // the first message blocks the timer thread, so the next ticks are late
+    #[tokio::test]
+    async fn send_interval_missed() {
+        for (missed, more) in [(Missed::Skip, false), (Missed::Burst, true)] {
+            let reason = run(async move {
+                let mut first = true;
+                let timer_ref = send_interval_opt(
+                    Duration::from_millis(10),
+                    myself(),
+                    move || {
+                        if std::mem::take(&mut first) {
+                            std::thread::sleep(Duration::from_millis(100));
+                        }
+                    },
+                    missed,
+                );
+
+                __receive().await;
+                tokio::time::sleep(Duration::from_millis(5)).await;
+                let pending = process_info(myself()).unwrap().message_queue_len;
+                cancel_timer(timer_ref);
+
+                assert_eq!(pending >= 5, more, "{:?}: {}", missed, pending);
+            })
+            .await;
+
+            assert_eq!(reason, ExitReason::Normal);
+        }
+    }
+}
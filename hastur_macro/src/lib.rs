@@ -3,50 +3,62 @@ use proc_macro2::{self};
 
 use quote::quote;
 
-use syn::parse_macro_input;
+use syn::{parse_macro_input, ItemFn, ReturnType, Type};
 
 mod parse;
 
 use parse::{Pattern, Receive};
 
 fn pattern(pattern: Pattern) -> proc_macro2::TokenStream {
+    let pat = &pattern.pat;
     let body = &pattern.body;
 
-    let value = if let Some(type_pattern) = &pattern.type_pattern {
-        quote! {
-            __in.downcast::<#type_pattern>().unwrap()
-        }
-    } else {
-        quote! {
-            __in
-        }
-    };
-
-    let assignment = if let Some(ident) = &pattern.ident {
-        quote! {
-            let #ident = #value;
-        }
-    } else {
-        quote! {
-            // no ident, no assignment needed
-        }
+    // a message that does not match is left in __in for the next patterns
+    let type_pattern = match pattern.types.as_slice() {
+        [type_pattern] => Some(type_pattern),
+        _ => None,
     };
 
-    let condition = if let Some(type_pattern) = &pattern.type_pattern {
-        quote! {
-            __in.is::<#type_pattern>()
-        }
+    // the message is bound as an envelope unless it has a single type
+    let condition = if pattern.types.is_empty() {
+        quote! { true }
     } else {
-        quote! {
-            true
-        }
+        let types = &pattern.types;
+        quote! { #(__in.is::<#types>())||* }
     };
 
-    quote! {
-        if #condition {
-            #assignment
-            break #body;
+    match (type_pattern, &pattern.guard) {
+        // the pattern binds by value, as in match, so the message is taken
+        // out and given back when the pattern or the guard fails
+        (Some(type_pattern), guard) => {
+            let guard = guard.as_ref().map(|guard| quote! { if #guard });
+
+            quote! {
+                #[allow(unreachable_patterns)]
+                if __in.is::<#type_pattern>() {
+                    let __boxed = __in.__into_box::<#type_pattern>();
+
+                    match *__boxed {
+                        #pat #guard => break #body,
+                        _ => __in = hastur::Envelope::__from_box(__boxed),
+                    }
+                }
+            }
         }
+        (None, None) => quote! {
+            if #condition {
+                let #pat = __in;
+                break #body;
+            }
+        },
+        (None, Some(guard)) => quote! {
+            if #condition {
+                match __in {
+                    #pat if #guard => break #body,
+                    _ => {}
+                }
+            }
+        },
     }
 }
 
@@ -60,10 +72,8 @@ pub fn receive(input: TokenStream) -> TokenStream {
     };
 
     let selective_save = quote! {
-        else {
-            __save_queue.push_front(__in);
-            continue;
-        }
+        __save_queue.push_front(__in);
+        continue;
     };
 
     let selective_restore = quote! {
@@ -80,23 +90,20 @@ pub fn receive(input: TokenStream) -> TokenStream {
             {
                 #prelude;
 
-                use async_std::future;
-                use std::time::{Instant, Duration};
-
-                let now = Instant::now();
-                let __duration: Duration = #duration.into();
+                let mut __after = hastur::__After::new(#duration);
 
+                #[allow(unused_braces, clippy::unused_unit)]
                 let result =
                     loop {
-                        let __in = future::timeout(__duration - now.elapsed(), hastur::__receive()).await;
-                        match __in {
-                            Err(_) => {
+                        match __after.receive().await {
+                            None => {
                                 break {
                                     #body
                                 };
                             },
-                            Ok(__in) => {
-                                #(#patterns)else* #selective_save
+                            #[allow(unused_mut)]
+                            Some(mut __in) => {
+                                #(#patterns)* #selective_save
                             }
                         }
                     };
@@ -110,10 +117,12 @@ pub fn receive(input: TokenStream) -> TokenStream {
             {
                 #prelude;
 
+                #[allow(unused_braces, clippy::unused_unit)]
                 let result =
                     loop {
-                        let __in = hastur::__receive().await;
-                        #(#patterns)else* #selective_save
+                        #[allow(unused_mut)]
+                        let mut __in = hastur::__receive().await;
+                        #(#patterns)* #selective_save
                     };
 
                 #selective_restore;
@@ -124,3 +133,65 @@ pub fn receive(input: TokenStream) -> TokenStream {
 
     TokenStream::from(result)
 }
+
+#[proc_macro_attribute]
+pub fn main(_attr: TokenStream, item: TokenStream) -> TokenStream {
+    let function = parse_macro_input!(item as ItemFn);
+
+    if function.sig.asyncness.is_none() {
+        return syn::Error::new_spanned(function.sig.fn_token, "the function must be async")
+            .to_compile_error()
+            .into();
+    }
+
+    // the exit reason of the root process is the result of the program
+    let unit = match &function.sig.output {
+        ReturnType::Default => true,
+        ReturnType::Type(_, output) => {
+            matches!(output.as_ref(), Type::Tuple(tuple) if tuple.elems.is_empty())
+        }
+    };
+
+    if !unit {
+        return syn::Error::new_spanned(
+            &function.sig.output,
+            "the function must return (), exit with an exit reason instead",
+        )
+        .to_compile_error()
+        .into();
+    }
+
+    if !function.sig.inputs.is_empty() {
+        return syn::Error::new_spanned(
+            &function.sig.inputs,
+            "the function must have no arguments",
+        )
+        .to_compile_error()
+        .into();
+    }
+
+    let generics = &function.sig.generics;
+    if !generics.params.is_empty() || generics.where_clause.is_some() {
+        let where_clause = &generics.where_clause;
+        return syn::Error::new_spanned(
+            quote! { #generics #where_clause },
+            "the function must not be generic",
+        )
+        .to_compile_error()
+        .into();
+    }
+
+    let attrs = &function.attrs;
+    let vis = &function.vis;
+    let ident = &function.sig.ident;
+    let body = &function.block;
+
+    let result = quote! {
+        #(#attrs)*
+        #vis fn #ident() -> hastur::ExitReason {
+            hastur::__main(async move #body)
+        }
+    };
+
+    TokenStream::from(result)
+}
@@ -1,11 +1,13 @@
 use syn::parse::{Error, Parse, ParseStream, Result};
 
-use syn::{custom_keyword, Block, Expr, Ident, Token, TypePath};
+use syn::{custom_keyword, Block, Expr, Pat, Token, Type};
 
 #[derive(Debug)]
 pub(crate) struct Pattern {
-    pub ident: Option<Ident>,
-    pub type_pattern: Option<TypePath>,
+    pub pat: Pat,
+    // empty for any type
+    pub types: Vec<Type>,
+    pub guard: Option<Expr>,
     pub body: Block,
 }
 
@@ -27,30 +29,46 @@ fn body(input: &mut ParseStream) -> Result<Block> {
 }
 
 fn parse_pattern(input: &mut ParseStream) -> Result<Pattern> {
-    let lookahead = input.lookahead1();
-    let ident = if lookahead.peek(Token![_]) {
-        input.parse::<Token![_]>()?;
-        None
-    } else {
-        Some(input.parse::<Ident>()?)
-    };
+    let pat = Pat::parse_single(input)?;
 
     input.parse::<Token![:]>()?;
 
     let lookahead = input.lookahead1();
-    let type_pattern = if lookahead.peek(Token![_]) {
+    let span = input.span();
+    let mut types = Vec::new();
+
+    if lookahead.peek(Token![_]) {
         input.parse::<Token![_]>()?;
-        None
     } else {
-        let type_pattern = input.parse::<TypePath>()?;
-        Some(type_pattern)
+        types.push(input.parse::<Type>()?);
+
+        while input.lookahead1().peek(Token![|]) {
+            input.parse::<Token![|]>()?;
+            types.push(input.parse::<Type>()?);
+        }
+    }
+
+    // an envelope can only be bound as a whole
+    if types.len() != 1 && !matches!(pat, Pat::Ident(_) | Pat::Wild(_)) {
+        return Err(Error::new(
+            span,
+            "a single message type is required to destructure",
+        ));
+    }
+
+    let guard = if input.lookahead1().peek(Token![if]) {
+        input.parse::<Token![if]>()?;
+        Some(Expr::parse_without_eager_brace(input)?)
+    } else {
+        None
     };
 
     let body = body(input)?;
 
     Ok(Pattern {
-        ident,
-        type_pattern,
+        pat,
+        types,
+        guard,
         body,
     })
 }